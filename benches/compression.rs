@@ -0,0 +1,323 @@
+//! Measures the throughput and quality of each compressor and quality preset on the synthetic
+//! images in `tests/common`, and writes a JSON report that runs can be compared with.
+//!
+//! Run with `cargo bench`. The report goes to `BC6H_BENCH_REPORT` if it's set, or to
+//! `bc6h-benchmark.json` in the target directory. The number of timed iterations of each case can
+//! be set with `BC6H_BENCH_ITERATIONS`. Use `WGPU_BACKEND` and `WGPU_ADAPTER_NAME` to pick the
+//! adapter, such as lavapipe on machines without a GPU.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use common::{ErrorMetrics, Gpu, Scene};
+use wgpu_bc6h_compression::{decode, profiling::Profiler, Quality};
+
+// The refined presets measure how the quality and speed scale with the number of partitions
+// between `Normal` (1) and `High` (all 32), and what optimizing and refining their endpoints
+// adds.
+const QUALITIES: [Quality; 11] = [
+    Quality::Fast,
+    Quality::Normal,
+    refined(1, true, 0),
+    refined(1, false, 1),
+    refined(2, false, 0),
+    refined(4, false, 0),
+    refined(4, true, 0),
+    refined(4, true, 1),
+    refined(4, true, 2),
+    refined(8, false, 0),
+    Quality::High,
+];
+
+const fn refined(partitions: u32, optimize_endpoints: bool, refinement_passes: u32) -> Quality {
+    Quality::Refined {
+        partitions,
+        optimize_endpoints,
+        refinement_passes,
+    }
+}
+
+// (compressor, extent) pairs with the same number of texels, so their throughputs compare.
+const CASES: [(&str, wgpu::Extent3d); 2] = [
+    (
+        "2d",
+        wgpu::Extent3d {
+            width: 512,
+            height: 512,
+            depth_or_array_layers: 1,
+        },
+    ),
+    (
+        "3d",
+        wgpu::Extent3d {
+            width: 128,
+            height: 128,
+            depth_or_array_layers: 16,
+        },
+    ),
+];
+
+struct Measurement {
+    scene: Scene,
+    compressor: &'static str,
+    extent: wgpu::Extent3d,
+    quality: Quality,
+    iterations: u32,
+    wall_time: Duration,
+    gpu_time: Option<Duration>,
+    errors: ErrorMetrics,
+}
+
+impl Measurement {
+    fn blocks(&self) -> u64 {
+        common::compressed_size(self.extent) / 16
+    }
+}
+
+fn main() {
+    let gpu = match Gpu::new() {
+        Some(gpu) => gpu,
+        None => {
+            println!("Skipping the benchmarks, as no adapter was found.");
+            return;
+        }
+    };
+
+    let iterations = std::env::var("BC6H_BENCH_ITERATIONS")
+        .ok()
+        .and_then(|iterations| iterations.parse().ok())
+        .unwrap_or(10u32)
+        .max(1);
+
+    let profiler = Profiler::new(&gpu.device, &gpu.queue, 1);
+
+    println!(
+        "{} ({:?}), {} iterations per case{}",
+        gpu.adapter_info.name,
+        gpu.adapter_info.backend,
+        iterations,
+        if profiler.is_some() {
+            ""
+        } else {
+            ", without timestamp queries"
+        }
+    );
+
+    let mut measurements = Vec::new();
+
+    for scene in Scene::ALL {
+        for (compressor, extent) in CASES {
+            let image = scene.image(extent.width, extent.height, extent.depth_or_array_layers);
+            let texture = image
+                .create_texture(&gpu.device, &gpu.queue, None)
+                .create_view(&wgpu::TextureViewDescriptor::default());
+
+            for quality in QUALITIES {
+                // Warms up the pipelines and gives the blocks to measure the quality of.
+                let buffer = gpu.compress(&texture, extent, quality, None);
+                let blocks = gpu.read(&buffer, common::compressed_size(extent));
+                let errors =
+                    ErrorMetrics::measure(&image, &decode::decode_blocks(&blocks, extent, false));
+
+                let mut wall_times = Vec::new();
+                let mut gpu_times = Vec::new();
+
+                for _ in 0..iterations {
+                    if let Some(profiler) = &profiler {
+                        profiler.reset();
+                    }
+
+                    let start = Instant::now();
+                    gpu.compress(&texture, extent, quality, profiler.as_ref());
+                    wall_times.push(start.elapsed());
+
+                    if let Some(profiler) = &profiler {
+                        let timings = pollster::block_on(profiler.read(&gpu.device)).unwrap();
+                        gpu_times.push(timings.iter().map(|timing| timing.duration).sum());
+                    }
+                }
+
+                let measurement = Measurement {
+                    scene,
+                    compressor,
+                    extent,
+                    quality,
+                    iterations,
+                    wall_time: median(&mut wall_times).unwrap(),
+                    gpu_time: median(&mut gpu_times),
+                    errors,
+                };
+
+                print_measurement(&measurement);
+                measurements.push(measurement);
+            }
+        }
+    }
+
+    let report_path = std::env::var_os("BC6H_BENCH_REPORT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("../bc6h-benchmark.json")
+        });
+
+    std::fs::write(&report_path, report(&gpu.adapter_info, &measurements)).unwrap();
+
+    println!("Wrote {}", report_path.display());
+}
+
+fn median(durations: &mut [Duration]) -> Option<Duration> {
+    durations.sort_unstable();
+    durations.get(durations.len() / 2).copied()
+}
+
+fn print_measurement(measurement: &Measurement) {
+    let time = measurement.gpu_time.unwrap_or(measurement.wall_time);
+
+    println!(
+        "{:<16} {} {:<28} {:>8.2} Mblocks/s  rmse {:<10.4e} log rmse {:<10.4e} max relative {:.4}",
+        measurement.scene.name(),
+        measurement.compressor,
+        quality_name(measurement.quality),
+        measurement.blocks() as f64 / time.as_secs_f64() / 1e6,
+        measurement.errors.rmse,
+        measurement.errors.log_rmse,
+        measurement.errors.max_relative_error,
+    );
+}
+
+fn quality_name(quality: Quality) -> String {
+    match quality {
+        Quality::Refined {
+            partitions,
+            optimize_endpoints,
+            refinement_passes,
+        } => {
+            let mut name = format!("refined-{}", partitions);
+            if optimize_endpoints {
+                name += "-optimized";
+            }
+            if refinement_passes > 0 {
+                write!(name, "-{}-passes", refinement_passes).unwrap();
+            }
+            name
+        }
+        quality => format!("{:?}", quality).to_lowercase(),
+    }
+}
+
+// Written by hand to avoid a dependency on serde for a flat list of numbers.
+fn report(adapter_info: &wgpu::AdapterInfo, measurements: &[Measurement]) -> String {
+    let mut json = String::new();
+
+    writeln!(json, "{{").unwrap();
+    writeln!(
+        json,
+        "  \"adapter\": {{ \"name\": {}, \"backend\": \"{:?}\", \"device_type\": \"{:?}\" }},",
+        json_string(&adapter_info.name),
+        adapter_info.backend,
+        adapter_info.device_type
+    )
+    .unwrap();
+    writeln!(json, "  \"measurements\": [").unwrap();
+
+    for (i, measurement) in measurements.iter().enumerate() {
+        let blocks = measurement.blocks() as f64;
+
+        writeln!(json, "    {{").unwrap();
+        writeln!(json, "      \"scene\": \"{}\",", measurement.scene.name()).unwrap();
+        writeln!(
+            json,
+            "      \"compressor\": \"{}\",",
+            measurement.compressor
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "      \"quality\": \"{}\",",
+            quality_name(measurement.quality)
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "      \"extent\": [{}, {}, {}],",
+            measurement.extent.width,
+            measurement.extent.height,
+            measurement.extent.depth_or_array_layers
+        )
+        .unwrap();
+        writeln!(json, "      \"iterations\": {},", measurement.iterations).unwrap();
+        writeln!(
+            json,
+            "      \"wall_seconds\": {},",
+            measurement.wall_time.as_secs_f64()
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "      \"wall_blocks_per_second\": {},",
+            json_number(Some(blocks / measurement.wall_time.as_secs_f64()))
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "      \"gpu_seconds\": {},",
+            json_number(measurement.gpu_time.map(|time| time.as_secs_f64()))
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "      \"gpu_blocks_per_second\": {},",
+            json_number(measurement.gpu_time.map(|time| blocks / time.as_secs_f64()))
+        )
+        .unwrap();
+        writeln!(json, "      \"rmse\": {},", measurement.errors.rmse).unwrap();
+        writeln!(json, "      \"log_rmse\": {},", measurement.errors.log_rmse).unwrap();
+        writeln!(
+            json,
+            "      \"max_relative_error\": {}",
+            measurement.errors.max_relative_error
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "    }}{}",
+            if i + 1 < measurements.len() { "," } else { "" }
+        )
+        .unwrap();
+    }
+
+    writeln!(json, "  ]").unwrap();
+    writeln!(json, "}}").unwrap();
+
+    json
+}
+
+fn json_string(string: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for character in string.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            character if character.is_control() => {
+                write!(escaped, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+// JSON has no infinities, which a pass too quick for the timestamps to tell apart would give.
+fn json_number(number: Option<f64>) -> String {
+    match number {
+        Some(number) if number.is_finite() => number.to_string(),
+        _ => "null".to_owned(),
+    }
+}
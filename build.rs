@@ -0,0 +1,41 @@
+//! With the `compile_shaders` feature, generates the SPIR-V from the WGSL shaders with
+//! `shaders/spirv.rs` instead of using the checked-in files in `shaders/compiled`, and fails the
+//! build if that fails. naga is a build dependency of the feature, so it needs no external tools
+//! and works with `--all-features`.
+//!
+//! Either way, `BC6H_SPIRV_DIR` is set to the directory the crate includes the SPIR-V from.
+
+use std::env;
+use std::path::PathBuf;
+
+#[cfg(feature = "compile_shaders")]
+#[path = "shaders/spirv.rs"]
+mod spirv;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(not(feature = "compile_shaders"))]
+    let spirv_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap())
+        .join("shaders")
+        .join("compiled");
+
+    #[cfg(feature = "compile_shaders")]
+    let spirv_dir = {
+        println!("cargo:rerun-if-changed=shaders");
+
+        let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+
+        for (name, entry_point, push_constants) in spirv::VARIANTS {
+            let spirv = spirv::compile(entry_point, *push_constants)
+                .unwrap_or_else(|error| panic!("failed to compile {}: {}", name, error));
+
+            std::fs::write(out_dir.join(name), spirv)
+                .unwrap_or_else(|error| panic!("failed to write {}: {}", name, error));
+        }
+
+        out_dir
+    };
+
+    println!("cargo:rustc-env=BC6H_SPIRV_DIR={}", spirv_dir.display());
+}
@@ -1,4 +1,9 @@
-use wgpu_bc6h_compression::{CompressionParams, Compressor2D, Compressor3D};
+use wgpu_bc6h_compression::{
+    profiling::Profiler,
+    requirements::{Configuration, DeviceRequirements},
+    sanitization::Sanitization,
+    CompressionParams, Compressor2D, Compressor3D, Quality,
+};
 
 fn main() {
     let mut args = std::env::args().skip(1);
@@ -20,20 +25,16 @@ fn main() {
         pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
             .unwrap();
 
+    let requirements = DeviceRequirements::new(&Configuration::for_adapter(&adapter));
+
     let (device, queue) = pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: None,
 
-            #[cfg(feature = "push_constants")]
-            features: wgpu::Features::PUSH_CONSTANTS,
-            #[cfg(not(feature = "push_constants"))]
-            features: wgpu::Features::empty(),
-
-            limits: wgpu::Limits {
-                #[cfg(feature = "push_constants")]
-                max_push_constant_size: if is_3d { 12 } else { 8 },
-                ..Default::default()
-            },
+            // Timestamps are only used to print how long the compression took.
+            features: requirements.features
+                | (adapter.features() & wgpu::Features::TIMESTAMP_QUERY),
+            limits: requirements.limits.using_resolution(adapter.limits()),
         },
         None,
     ))
@@ -84,11 +85,19 @@ fn main() {
     let mut command_encoder =
         device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
+    let profiler = Profiler::new(&device, &queue, 1);
+
     let params = CompressionParams {
         bind_group_label: None,
         sampler: &sampler,
         texture: &texture_view,
         extent,
+        quality: Quality::default(),
+        source_mip_level: 0,
+        source_base_layer: 0,
+        profiler: profiler.as_ref(),
+        sanitization: Sanitization::default(),
+        corrected_texel_counter: None,
     };
 
     if is_3d {
@@ -116,8 +125,27 @@ fn main() {
 
     command_encoder.copy_buffer_to_buffer(&target_buffer, 0, &mappable_buffer, 0, buffer_size);
 
+    if let Some(profiler) = &profiler {
+        profiler.resolve(&mut command_encoder);
+    }
+
     queue.submit(Some(command_encoder.finish()));
 
+    if let Some(profiler) = &profiler {
+        for timing in pollster::block_on(profiler.read(&device)).unwrap() {
+            match timing.blocks_per_second() {
+                Some(blocks_per_second) => println!(
+                    "Compressed {} blocks in {:?} ({:.0} blocks/s)",
+                    timing.blocks, timing.duration, blocks_per_second
+                ),
+                None => println!(
+                    "Compressed {} blocks in {:?}",
+                    timing.blocks, timing.duration
+                ),
+            }
+        }
+    }
+
     let slice = mappable_buffer.slice(..);
 
     let map_future = slice.map_async(wgpu::MapMode::Read);
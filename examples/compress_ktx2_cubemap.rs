@@ -1,5 +1,9 @@
 use wgpu::util::DeviceExt;
-use wgpu_bc6h_compression::{CompressionParams, Compressor3D};
+use wgpu_bc6h_compression::{
+    requirements::{Configuration, DeviceRequirements},
+    sanitization::Sanitization,
+    CompressionParams, Compressor3D, Quality,
+};
 
 fn main() {
     let mut args = std::env::args().skip(1);
@@ -31,11 +35,13 @@ fn main() {
         pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
             .unwrap();
 
+    let requirements = DeviceRequirements::new(&Configuration::for_adapter(&adapter));
+
     let (device, queue) = pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: None,
-            features: wgpu::Features::empty(),
-            limits: wgpu::Limits::default(),
+            features: requirements.features,
+            limits: requirements.limits.using_resolution(adapter.limits()),
         },
         None,
     ))
@@ -87,7 +93,7 @@ fn main() {
                             usage: wgpu::TextureUsages::TEXTURE_BINDING
                                 | wgpu::TextureUsages::COPY_DST,
                         },
-                        &level.bytes,
+                        level.data,
                     )
                     .create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -113,6 +119,12 @@ fn main() {
                     sampler: &sampler,
                     texture: &texture_view,
                     extent,
+                    quality: Quality::default(),
+                    source_mip_level: 0,
+                    source_base_layer: 0,
+                    profiler: None,
+                    sanitization: Sanitization::default(),
+                    corrected_texel_counter: None,
                 };
 
                 Compressor3D::new(&device).compress_to_buffer(
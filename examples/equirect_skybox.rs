@@ -0,0 +1,135 @@
+use wgpu_bc6h_compression::{
+    equirect::{EquirectToCubemap, Filter, ProjectionParams},
+    radiance,
+    requirements::{Configuration, DeviceRequirements},
+    sanitization::Sanitization,
+    CompressionParams, Compressor3D, Quality, TextureParams,
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let input_filename = args.next().unwrap();
+    let output_filename = args.next().unwrap();
+    let face_size: u32 = args.next().map_or(512, |arg| arg.parse().unwrap());
+
+    let panorama = radiance::read_hdr(std::io::BufReader::new(
+        std::fs::File::open(&input_filename).unwrap(),
+    ))
+    .unwrap();
+
+    let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .unwrap();
+
+    let requirements = DeviceRequirements::new(&Configuration::for_adapter(&adapter));
+
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+
+            features: requirements.features,
+            limits: requirements.limits.using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .unwrap();
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+    let panorama_view = panorama
+        .create_texture(&device, &queue, Some("panorama"))
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut command_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    let cubemap = EquirectToCubemap::new(&device).project(
+        &device,
+        &mut command_encoder,
+        &ProjectionParams {
+            bind_group_label: None,
+            texture: &panorama_view,
+            face_size,
+            filter: Filter::Bicubic,
+        },
+        &TextureParams {
+            label: Some("cubemap"),
+            usage: wgpu::TextureUsages::empty(),
+        },
+    );
+
+    let extent = wgpu::Extent3d {
+        width: face_size,
+        height: face_size,
+        depth_or_array_layers: 6,
+    };
+
+    let buffer_size = extent.width as u64 * extent.height as u64 * 6;
+
+    let target_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    Compressor3D::new(&device).compress_to_buffer(
+        &device,
+        &mut command_encoder,
+        &CompressionParams {
+            bind_group_label: None,
+            sampler: &sampler,
+            texture: &cubemap.create_view(&wgpu::TextureViewDescriptor::default()),
+            extent,
+            quality: Quality::default(),
+            source_mip_level: 0,
+            source_base_layer: 0,
+            profiler: None,
+            sanitization: Sanitization::default(),
+            corrected_texel_counter: None,
+        },
+        &target_buffer,
+    );
+
+    let mappable_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    command_encoder.copy_buffer_to_buffer(&target_buffer, 0, &mappable_buffer, 0, buffer_size);
+
+    queue.submit(Some(command_encoder.finish()));
+
+    let slice = mappable_buffer.slice(..);
+
+    let map_future = slice.map_async(wgpu::MapMode::Read);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    pollster::block_on(map_future).unwrap();
+
+    let mut compressed_dds = ddsfile::Dds::new_dxgi(ddsfile::NewDxgiParams {
+        width: face_size,
+        height: face_size,
+        depth: None,
+        format: ddsfile::DxgiFormat::BC6H_UF16,
+        mipmap_levels: None,
+        array_layers: Some(6),
+        is_cubemap: true,
+        caps2: Some(ddsfile::Caps2::CUBEMAP | ddsfile::Caps2::CUBEMAP_ALLFACES),
+        resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+        alpha_mode: ddsfile::AlphaMode::Unknown,
+    })
+    .unwrap();
+
+    // Without mips, the faces are laid out one after the other just like the slices.
+    compressed_dds.data = slice.get_mapped_range().to_vec();
+
+    compressed_dds
+        .write(&mut std::fs::File::create(output_filename).unwrap())
+        .unwrap();
+}
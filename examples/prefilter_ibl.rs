@@ -0,0 +1,188 @@
+use wgpu_bc6h_compression::{
+    equirect::{EquirectToCubemap, Filter, ProjectionParams},
+    ibl::{MipChainParams, Sh9, ShParams, ShProjector, SpecularPrefilter},
+    radiance,
+    requirements::{Configuration, DeviceRequirements},
+    Compressor3D, Quality, TextureParams,
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let input_filename = args.next().unwrap();
+    let output_filename = args.next().unwrap();
+    let face_size: u32 = args.next().map_or(256, |arg| arg.parse().unwrap());
+    let sample_count: u32 = args.next().map_or(1024, |arg| arg.parse().unwrap());
+
+    let panorama = radiance::read_hdr(std::io::BufReader::new(
+        std::fs::File::open(&input_filename).unwrap(),
+    ))
+    .unwrap();
+
+    let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .unwrap();
+
+    let requirements = DeviceRequirements::new(&Configuration::for_adapter(&adapter));
+
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+
+            features: requirements.features,
+            limits: requirements.limits.using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .unwrap();
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+    let panorama_view = panorama
+        .create_texture(&device, &queue, Some("panorama"))
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut command_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    let cubemap = EquirectToCubemap::new(&device).project(
+        &device,
+        &mut command_encoder,
+        &ProjectionParams {
+            bind_group_label: None,
+            texture: &panorama_view,
+            face_size,
+            filter: Filter::Bilinear,
+        },
+        &TextureParams {
+            label: Some("cubemap"),
+            usage: wgpu::TextureUsages::empty(),
+        },
+    );
+
+    let cubemap_view = cubemap.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let levels = SpecularPrefilter::new(&device).compress_mip_chain(
+        &device,
+        &mut command_encoder,
+        &Compressor3D::new(&device),
+        &MipChainParams {
+            bind_group_label: None,
+            texture: &cubemap_view,
+            sampler: &sampler,
+            face_size,
+            level_count: u32::MAX,
+            sample_count,
+            quality: Quality::default(),
+        },
+    );
+
+    let coefficients_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: ShProjector::COEFFICIENTS_SIZE,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    ShProjector::new(&device).project_to_buffer(
+        &device,
+        &mut command_encoder,
+        &ShParams {
+            bind_group_label: None,
+            texture: &cubemap_view,
+            face_size,
+        },
+        &coefficients_buffer,
+    );
+
+    let mappable_coefficients_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: ShProjector::COEFFICIENTS_SIZE,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    command_encoder.copy_buffer_to_buffer(
+        &coefficients_buffer,
+        0,
+        &mappable_coefficients_buffer,
+        0,
+        ShProjector::COEFFICIENTS_SIZE,
+    );
+
+    let mappable_buffers: Vec<_> = levels
+        .iter()
+        .map(|level| {
+            let size = level.extent.width as u64 * level.extent.height as u64 * 6;
+
+            let mappable_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+
+            command_encoder.copy_buffer_to_buffer(&level.buffer, 0, &mappable_buffer, 0, size);
+
+            mappable_buffer
+        })
+        .collect();
+
+    queue.submit(Some(command_encoder.finish()));
+
+    let coefficients_slice = mappable_coefficients_buffer.slice(..);
+    let coefficients_map_future = coefficients_slice.map_async(wgpu::MapMode::Read);
+
+    let map_futures: Vec<_> = mappable_buffers
+        .iter()
+        .map(|buffer| buffer.slice(..).map_async(wgpu::MapMode::Read))
+        .collect();
+
+    device.poll(wgpu::Maintain::Wait);
+
+    pollster::block_on(coefficients_map_future).unwrap();
+
+    println!(
+        "{:#?}",
+        Sh9::from_bytes(&coefficients_slice.get_mapped_range())
+    );
+
+    let level_bytes: Vec<Vec<u8>> = mappable_buffers
+        .iter()
+        .zip(map_futures)
+        .map(|(buffer, map_future)| {
+            pollster::block_on(map_future).unwrap();
+            buffer.slice(..).get_mapped_range().to_vec()
+        })
+        .collect();
+
+    let mut compressed_dds = ddsfile::Dds::new_dxgi(ddsfile::NewDxgiParams {
+        width: face_size,
+        height: face_size,
+        depth: None,
+        format: ddsfile::DxgiFormat::BC6H_UF16,
+        mipmap_levels: Some(levels.len() as u32),
+        array_layers: Some(6),
+        is_cubemap: true,
+        caps2: Some(ddsfile::Caps2::CUBEMAP | ddsfile::Caps2::CUBEMAP_ALLFACES),
+        resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+        alpha_mode: ddsfile::AlphaMode::Unknown,
+    })
+    .unwrap();
+
+    // DDS stores each face with its full mip chain, one face after the other.
+    compressed_dds.data = (0..6)
+        .flat_map(|face| {
+            level_bytes.iter().flat_map(move |bytes| {
+                let face_size = bytes.len() / 6;
+                &bytes[face * face_size..(face + 1) * face_size]
+            })
+        })
+        .copied()
+        .collect();
+
+    compressed_dds
+        .write(&mut std::fs::File::create(output_filename).unwrap())
+        .unwrap();
+}
@@ -0,0 +1,102 @@
+use wgpu_bc6h_compression::{
+    decode, pfm,
+    requirements::{Configuration, DeviceRequirements},
+    sanitization::Sanitization,
+    CompressionParams, Compressor2D, Quality,
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let input_filename = args.next().unwrap();
+    let output_filename = args.next().unwrap();
+
+    let image = pfm::read_pfm(std::io::BufReader::new(
+        std::fs::File::open(&input_filename).unwrap(),
+    ))
+    .unwrap()
+    .padded_to_blocks();
+
+    let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .unwrap();
+
+    let requirements = DeviceRequirements::new(&Configuration::for_adapter(&adapter));
+
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+
+            features: requirements.features,
+            limits: requirements.limits.using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .unwrap();
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+    let extent = image.extent();
+
+    let texture_view = image
+        .create_texture(&device, &queue, Some("uncompressed texture"))
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    let buffer_size = extent.width as u64 * extent.height as u64;
+
+    let target_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let mut command_encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    Compressor2D::new(&device).compress_to_buffer(
+        &device,
+        &mut command_encoder,
+        &CompressionParams {
+            bind_group_label: None,
+            sampler: &sampler,
+            texture: &texture_view,
+            extent,
+            quality: Quality::default(),
+            source_mip_level: 0,
+            source_base_layer: 0,
+            profiler: None,
+            sanitization: Sanitization::default(),
+            corrected_texel_counter: None,
+        },
+        &target_buffer,
+    );
+
+    let mappable_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: buffer_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    command_encoder.copy_buffer_to_buffer(&target_buffer, 0, &mappable_buffer, 0, buffer_size);
+
+    queue.submit(Some(command_encoder.finish()));
+
+    let slice = mappable_buffer.slice(..);
+
+    let map_future = slice.map_async(wgpu::MapMode::Read);
+
+    device.poll(wgpu::Maintain::Wait);
+
+    pollster::block_on(map_future).unwrap();
+
+    let decoded = decode::decode_blocks(&slice.get_mapped_range(), extent, false);
+
+    pfm::write_pfm(
+        std::io::BufWriter::new(std::fs::File::create(output_filename).unwrap()),
+        &decoded,
+    )
+    .unwrap();
+}
@@ -0,0 +1,1119 @@
+// The block encoder, a WGSL port of GPURealTimeBC6H's HLSL shader. It is prepended to one of the
+// `compress_*.wgsl` entry points, which declare the bindings, fill `texels` and write out
+// `encoded_block`. The SPIR-V in `shaders/compiled` is generated from them, see `shaders/spirv.rs`.
+//
+// The HLSL's `f32tof16` and `f16tof32` are emulated with `pack2x16float` and `unpack2x16float`.
+// WGSL has no `inout` arrays, so the texels and the encoded block live in private variables
+// instead of being passed around.
+
+let HALF_MAX: f32 = 65504.0;
+// The bit pattern of HALF_MAX, the largest finite half.
+let HALF_MAX_BITS: f32 = 31743.0;
+let PATTERN_NUM: u32 = 32u;
+
+// Bits of `Constants.flags`, set from the `Quality` on the Rust side.
+let FLAG_ENCODE_P2: u32 = 1u;
+// Set from the `Sanitization`.
+let FLAG_SANITIZE: u32 = 2u;
+let FLAG_NAN_TO_AVERAGE: u32 = 4u;
+// Set when a `CorrectedTexelCounter` is passed.
+let FLAG_COUNT_CORRECTED: u32 = 8u;
+// Refine the endpoints of P2 modes like those of P1, from `Quality::Refined` and `Quality::High`.
+let FLAG_OPTIMIZE_ENDPOINTS_P2: u32 = 16u;
+// How many times to refit the endpoints to indices picked by their actual error, from
+// `Quality::Refined` and `Quality::High`.
+let FLAGS_REFINEMENT_PASSES_SHIFT: u32 = 16u;
+let FLAGS_REFINEMENT_PASSES_MASK: u32 = 0x7u;
+// How many of the best scoring partitions to fully encode, less one, from `Quality::Refined`.
+let FLAGS_PARTITIONS_SHIFT: u32 = 8u;
+let FLAGS_PARTITIONS_MASK: u32 = 0x1Fu;
+
+// Fetched texels of the current 4x4 block:
+// 0 1 2 3
+// 4 5 6 7
+// 8 9 10 11
+// 12 13 14 15
+var<private> texels: array<vec3<f32>, 16>;
+var<private> encoded_block: vec4<u32>;
+var<private> block_msle: f32;
+// The indices to fit endpoints to, and those picked by their actual error.
+var<private> fit_indices: array<u32, 16>;
+var<private> refined_indices: array<u32, 16>;
+
+// Shared by the workgroup of `encode_block_cooperatively`, one candidate per thread.
+var<workgroup> shared_texels: array<vec3<f32>, 16>;
+var<workgroup> candidate_msle: array<f32, 32>;
+var<workgroup> candidate_blocks: array<vec4<u32>, 32>;
+
+struct Endpoints {
+    min: vec3<f32>;
+    max: vec3<f32>;
+};
+
+fn f32_to_f16(x: f32) -> f32 {
+    return f32(pack2x16float(vec2<f32>(x, 0.0)) & 0xFFFFu);
+}
+
+fn f32_to_f16_3(x: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(f32_to_f16(x.x), f32_to_f16(x.y), f32_to_f16(x.z));
+}
+
+fn f16_to_f32_3(x: vec3<u32>) -> vec3<f32> {
+    return vec3<f32>(unpack2x16float(x.x).x, unpack2x16float(x.y).x, unpack2x16float(x.z).x);
+}
+
+fn refinement_passes_of(flags: u32) -> u32 {
+    return (flags >> FLAGS_REFINEMENT_PASSES_SHIFT) & FLAGS_REFINEMENT_PASSES_MASK;
+}
+
+fn calc_msle_channels(a: vec3<f32>, b: vec3<f32>) -> vec3<f32> {
+    let delta = log2((b + 1.0) / (a + 1.0));
+    let luminance_weights = vec3<f32>(0.299, 0.587, 0.114);
+    return delta * delta * luminance_weights;
+}
+
+fn calc_msle(a: vec3<f32>, b: vec3<f32>) -> f32 {
+    let delta_sq = calc_msle_channels(a, b);
+    return delta_sq.x + delta_sq.y + delta_sq.z;
+}
+
+fn pattern_fixup_id(i: u32) -> u32 {
+    var ret = 15u;
+    if (((3441033216u >> i) & 1u) != 0u) {
+        ret = 2u;
+    }
+    if (((845414400u >> i) & 1u) != 0u) {
+        ret = 8u;
+    }
+    return ret;
+}
+
+fn pattern(p: u32, i: u32) -> u32 {
+    let p2 = p / 2u;
+    let p3 = p - p2 * 2u;
+
+    var enc = 0u;
+    switch (i32(p2)) {
+        case 0: { enc = 2290666700u; }
+        case 1: { enc = 3972591342u; }
+        case 2: { enc = 4276930688u; }
+        case 3: { enc = 3967876808u; }
+        case 4: { enc = 4293707776u; }
+        case 5: { enc = 3892379264u; }
+        case 6: { enc = 4278255592u; }
+        case 7: { enc = 4026597360u; }
+        case 8: { enc = 9369360u; }
+        case 9: { enc = 147747072u; }
+        case 10: { enc = 1930428556u; }
+        case 11: { enc = 2362323200u; }
+        case 12: { enc = 823134348u; }
+        case 13: { enc = 913073766u; }
+        case 14: { enc = 267393000u; }
+        case 15: { enc = 966553998u; }
+        default: {}
+    }
+
+    if (p3 != 0u) {
+        enc = enc >> 16u;
+    }
+    return (enc >> i) & 1u;
+}
+
+fn quantize7(x: vec3<f32>) -> vec3<f32> {
+    return (f32_to_f16_3(x) * 128.0) / f32(0x7bff + 1);
+}
+
+fn quantize9(x: vec3<f32>) -> vec3<f32> {
+    return (f32_to_f16_3(x) * 512.0) / f32(0x7bff + 1);
+}
+
+fn quantize10(x: vec3<f32>) -> vec3<f32> {
+    return (f32_to_f16_3(x) * 1024.0) / f32(0x7bff + 1);
+}
+
+fn unquantize7(x: vec3<f32>) -> vec3<f32> {
+    return (x * 65536.0 + f32(0x8000)) / 128.0;
+}
+
+fn unquantize9(x: vec3<f32>) -> vec3<f32> {
+    return (x * 65536.0 + f32(0x8000)) / 512.0;
+}
+
+fn unquantize10(x: vec3<f32>) -> vec3<f32> {
+    return (x * 65536.0 + f32(0x8000)) / 1024.0;
+}
+
+fn finish_unquantize(endpoint0_unq: vec3<f32>, endpoint1_unq: vec3<f32>, weight: f32) -> vec3<f32> {
+    let comp = (endpoint0_unq * (64.0 - weight) + endpoint1_unq * weight + 32.0) * (31.0 / 4096.0);
+    return f16_to_f32_3(vec3<u32>(comp));
+}
+
+fn compute_index3(texel_pos: f32, end_point0_pos: f32, end_point1_pos: f32) -> u32 {
+    let r = (texel_pos - end_point0_pos) / (end_point1_pos - end_point0_pos);
+    return u32(clamp(r * 6.98182 + 0.00909 + 0.5, 0.0, 7.0));
+}
+
+fn compute_index4(texel_pos: f32, end_point0_pos: f32, end_point1_pos: f32) -> u32 {
+    let r = (texel_pos - end_point0_pos) / (end_point1_pos - end_point0_pos);
+    return u32(clamp(r * 14.93333 + 0.03333 + 0.5, 0.0, 15.0));
+}
+
+fn sign_extend(v1: vec3<f32>, mask: u32, sign_flag: u32) -> vec3<f32> {
+    let v = vec3<i32>(v1);
+    let extended = (v & vec3<i32>(i32(mask)))
+        | select(vec3<i32>(0), vec3<i32>(i32(sign_flag)), v < vec3<i32>(0));
+    return vec3<f32>(extended);
+}
+
+// Refine endpoints by insetting bounding box in log2 RGB space
+fn inset_color_bbox_p1(block_min: vec3<f32>, block_max: vec3<f32>) -> Endpoints {
+    var refined_block_min = block_max;
+    var refined_block_max = block_min;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        refined_block_min = min(
+            refined_block_min,
+            select(texels[i], refined_block_min, texels[i] == block_min)
+        );
+        refined_block_max = max(
+            refined_block_max,
+            select(texels[i], refined_block_max, texels[i] == block_max)
+        );
+    }
+
+    let log_refined_block_max = log2(refined_block_max + 1.0);
+    let log_refined_block_min = log2(refined_block_min + 1.0);
+
+    var log_block_max = log2(block_max + 1.0);
+    var log_block_min = log2(block_min + 1.0);
+    let log_block_max_ext = (log_block_max - log_block_min) * (1.0 / 32.0);
+
+    log_block_min = log_block_min + min(log_refined_block_min - log_block_min, log_block_max_ext);
+    log_block_max = log_block_max - min(log_block_max - log_refined_block_max, log_block_max_ext);
+
+    return Endpoints(exp2(log_block_min) - 1.0, exp2(log_block_max) - 1.0);
+}
+
+// Refine endpoints by insetting bounding box in log2 RGB space
+fn inset_color_bbox_p2(
+    pattern_index: u32,
+    pattern_selector: u32,
+    block_min: vec3<f32>,
+    block_max: vec3<f32>,
+) -> Endpoints {
+    var refined_block_min = block_max;
+    var refined_block_max = block_min;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        if (pattern(pattern_index, i) == pattern_selector) {
+            refined_block_min = min(
+                refined_block_min,
+                select(texels[i], refined_block_min, texels[i] == block_min)
+            );
+            refined_block_max = max(
+                refined_block_max,
+                select(texels[i], refined_block_max, texels[i] == block_max)
+            );
+        }
+    }
+
+    let log_refined_block_max = log2(refined_block_max + 1.0);
+    let log_refined_block_min = log2(refined_block_min + 1.0);
+
+    var log_block_max = log2(block_max + 1.0);
+    var log_block_min = log2(block_min + 1.0);
+    let log_block_max_ext = (log_block_max - log_block_min) * (1.0 / 32.0);
+
+    log_block_min = log_block_min + min(log_refined_block_min - log_block_min, log_block_max_ext);
+    log_block_max = log_block_max - min(log_block_max - log_refined_block_max, log_block_max_ext);
+
+    return Endpoints(exp2(log_block_min) - 1.0, exp2(log_block_max) - 1.0);
+}
+
+// Least squares fit of the endpoints to the block indices in `fit_indices`
+fn fit_endpoints_p1(block_min: vec3<f32>, block_max: vec3<f32>) -> Endpoints {
+    var alpha_texel_sum = vec3<f32>(0.0);
+    var beta_texel_sum = vec3<f32>(0.0);
+    var alpha_beta_sum = 0.0;
+    var alpha_sq_sum = 0.0;
+    var beta_sq_sum = 0.0;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let beta = clamp(f32(fit_indices[i]) / 15.0, 0.0, 1.0);
+        let alpha = 1.0 - beta;
+
+        let texel_f16 = f32_to_f16_3(texels[i]);
+        alpha_texel_sum = alpha_texel_sum + alpha * texel_f16;
+        beta_texel_sum = beta_texel_sum + beta * texel_f16;
+
+        alpha_beta_sum = alpha_beta_sum + alpha * beta;
+
+        alpha_sq_sum = alpha_sq_sum + alpha * alpha;
+        beta_sq_sum = beta_sq_sum + beta * beta;
+    }
+
+    let det = alpha_sq_sum * beta_sq_sum - alpha_beta_sum * alpha_beta_sum;
+
+    if (abs(det) > 0.00001) {
+        let det_rcp = 1.0 / det;
+        return Endpoints(
+            f16_to_f32_3(vec3<u32>(clamp(
+                det_rcp * (alpha_texel_sum * beta_sq_sum - beta_texel_sum * alpha_beta_sum),
+                vec3<f32>(0.0),
+                vec3<f32>(HALF_MAX_BITS)
+            ))),
+            f16_to_f32_3(vec3<u32>(clamp(
+                det_rcp * (beta_texel_sum * alpha_sq_sum - alpha_texel_sum * alpha_beta_sum),
+                vec3<f32>(0.0),
+                vec3<f32>(HALF_MAX_BITS)
+            )))
+        );
+    }
+
+    return Endpoints(block_min, block_max);
+}
+
+// Least squares optimization to find best endpoints for the selected block indices
+fn optimize_endpoints_p1(block_min: vec3<f32>, block_max: vec3<f32>) -> Endpoints {
+    var block_dir = block_max - block_min;
+    block_dir = block_dir / (block_dir.x + block_dir.y + block_dir.z);
+
+    let end_point0_pos = f32_to_f16(dot(block_min, block_dir));
+    let end_point1_pos = f32_to_f16(dot(block_max, block_dir));
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let texel_pos = f32_to_f16(dot(texels[i], block_dir));
+        fit_indices[i] = compute_index4(texel_pos, end_point0_pos, end_point1_pos);
+    }
+
+    return fit_endpoints_p1(block_min, block_max);
+}
+
+// Least squares fit of the endpoints of one region to the block indices in `fit_indices`
+fn fit_endpoints_p2(
+    pattern_index: u32,
+    pattern_selector: u32,
+    block_min: vec3<f32>,
+    block_max: vec3<f32>,
+) -> Endpoints {
+    var alpha_texel_sum = vec3<f32>(0.0);
+    var beta_texel_sum = vec3<f32>(0.0);
+    var alpha_beta_sum = 0.0;
+    var alpha_sq_sum = 0.0;
+    var beta_sq_sum = 0.0;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        if (pattern(pattern_index, i) == pattern_selector) {
+            let beta = clamp(f32(fit_indices[i]) / 7.0, 0.0, 1.0);
+            let alpha = 1.0 - beta;
+
+            let texel_f16 = f32_to_f16_3(texels[i]);
+            alpha_texel_sum = alpha_texel_sum + alpha * texel_f16;
+            beta_texel_sum = beta_texel_sum + beta * texel_f16;
+
+            alpha_beta_sum = alpha_beta_sum + alpha * beta;
+
+            alpha_sq_sum = alpha_sq_sum + alpha * alpha;
+            beta_sq_sum = beta_sq_sum + beta * beta;
+        }
+    }
+
+    let det = alpha_sq_sum * beta_sq_sum - alpha_beta_sum * alpha_beta_sum;
+
+    if (abs(det) > 0.00001) {
+        let det_rcp = 1.0 / det;
+        // The texels are fitted as half bit patterns, so the endpoints are clamped to that of the
+        // largest finite half. Anything above it would unpack to an infinity or a NaN.
+        let optimized_min = f16_to_f32_3(vec3<u32>(clamp(
+            det_rcp * (alpha_texel_sum * beta_sq_sum - beta_texel_sum * alpha_beta_sum),
+            vec3<f32>(0.0),
+            vec3<f32>(HALF_MAX_BITS)
+        )));
+        let optimized_max = f16_to_f32_3(vec3<u32>(clamp(
+            det_rcp * (beta_texel_sum * alpha_sq_sum - alpha_texel_sum * alpha_beta_sum),
+            vec3<f32>(0.0),
+            vec3<f32>(HALF_MAX_BITS)
+        )));
+
+        // With few texels to a region, the fit can cross or collapse the endpoints, which would
+        // leave no direction to index along. Keep them ordered like the bounding box.
+        if (all(optimized_max >= optimized_min) && any(optimized_max > optimized_min)) {
+            return Endpoints(optimized_min, optimized_max);
+        }
+    }
+
+    return Endpoints(block_min, block_max);
+}
+
+// Least squares optimization to find best endpoints for the selected block indices
+fn optimize_endpoints_p2(
+    pattern_index: u32,
+    pattern_selector: u32,
+    block_min: vec3<f32>,
+    block_max: vec3<f32>,
+) -> Endpoints {
+    var block_dir = block_max - block_min;
+
+    // A region of one colour has no direction, and needs no fitting.
+    if (block_dir.x + block_dir.y + block_dir.z <= 0.0) {
+        return Endpoints(block_min, block_max);
+    }
+
+    block_dir = block_dir / (block_dir.x + block_dir.y + block_dir.z);
+
+    let end_point0_pos = f32_to_f16(dot(block_min, block_dir));
+    let end_point1_pos = f32_to_f16(dot(block_max, block_dir));
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let texel_pos = f32_to_f16(dot(texels[i], block_dir));
+        fit_indices[i] = compute_index3(texel_pos, end_point0_pos, end_point1_pos);
+    }
+
+    return fit_endpoints_p2(pattern_index, pattern_selector, block_min, block_max);
+}
+
+// Picks the index of each texel with the lowest error against the quantized endpoints into
+// `refined_indices`, rather than projecting it onto the line between them. The first texel is
+// limited to the first half of the indices, which the endpoints were already swapped for.
+fn compute_indices_p1(endpoint0_unq: vec3<f32>, endpoint1_unq: vec3<f32>) -> f32 {
+    var msle = 0.0;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let index_num = select(16u, 8u, i == 0u);
+        var best_msle = 3.4e38;
+        var best_index = 0u;
+
+        for (
+            var index_candidate: u32 = 0u;
+            index_candidate < index_num;
+            index_candidate = index_candidate + 1u
+        ) {
+            let weight = floor((f32(index_candidate) * 64.0) / 15.0 + 0.5);
+            let texel_msle =
+                calc_msle(texels[i], finish_unquantize(endpoint0_unq, endpoint1_unq, weight));
+            if (texel_msle < best_msle) {
+                best_msle = texel_msle;
+                best_index = index_candidate;
+            }
+        }
+
+        refined_indices[i] = best_index;
+        msle = msle + best_msle;
+    }
+
+    return msle;
+}
+
+// Rounds each channel of the quantized endpoints down or up, whichever has the lower error with
+// the indices in `fit_indices`. The error is a sum over the channels, so each can be rounded on
+// its own.
+fn round_endpoints_p1(endpoint0: vec3<f32>, endpoint1: vec3<f32>) -> Endpoints {
+    var best_endpoint0 = floor(endpoint0);
+    var best_endpoint1 = floor(endpoint1);
+    var best_msle = vec3<f32>(3.4e38);
+
+    for (var rounding: u32 = 0u; rounding < 4u; rounding = rounding + 1u) {
+        let candidate0 = min(
+            select(floor(endpoint0), ceil(endpoint0), (rounding & 1u) != 0u),
+            vec3<f32>(1023.0)
+        );
+        let candidate1 = min(
+            select(floor(endpoint1), ceil(endpoint1), (rounding & 2u) != 0u),
+            vec3<f32>(1023.0)
+        );
+        let candidate0_unq = unquantize10(candidate0);
+        let candidate1_unq = unquantize10(candidate1);
+
+        var msle = vec3<f32>(0.0);
+        for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+            let weight = floor((f32(fit_indices[i]) * 64.0) / 15.0 + 0.5);
+            let texel_unc = finish_unquantize(candidate0_unq, candidate1_unq, weight);
+            msle = msle + calc_msle_channels(texels[i], texel_unc);
+        }
+
+        let better = msle < best_msle;
+        best_endpoint0 = select(best_endpoint0, candidate0, better);
+        best_endpoint1 = select(best_endpoint1, candidate1, better);
+        best_msle = min(msle, best_msle);
+    }
+
+    return Endpoints(best_endpoint0, best_endpoint1);
+}
+
+struct RefinedP1 {
+    endpoint0: vec3<f32>;
+    endpoint1: vec3<f32>;
+    msle: f32;
+};
+
+// Alternates between picking the indices with the lowest error for the quantized endpoints and
+// refitting the endpoints to those indices, keeping whichever pair has the lowest error. Leaves
+// its indices in `refined_indices`.
+fn refine_p1(
+    refinement_passes: u32,
+    block_min: vec3<f32>,
+    block_max: vec3<f32>,
+    endpoint0: vec3<f32>,
+    endpoint1: vec3<f32>,
+) -> RefinedP1 {
+    var fitted = Endpoints(block_min, block_max);
+    var candidate0 = floor(endpoint0);
+    var candidate1 = floor(endpoint1);
+    var refined = RefinedP1(candidate0, candidate1, 3.4e38);
+    var best_indices: array<u32, 16>;
+
+    for (var refinement: u32 = 0u; refinement <= refinement_passes; refinement = refinement + 1u) {
+        let candidate_msle = compute_indices_p1(unquantize10(candidate0), unquantize10(candidate1));
+
+        if (candidate_msle < refined.msle) {
+            refined = RefinedP1(candidate0, candidate1, candidate_msle);
+            best_indices = refined_indices;
+        }
+
+        if (refinement < refinement_passes) {
+            fit_indices = refined_indices;
+            fitted = fit_endpoints_p1(fitted.min, fitted.max);
+            let rounded = round_endpoints_p1(quantize10(fitted.min), quantize10(fitted.max));
+            candidate0 = rounded.min;
+            candidate1 = rounded.max;
+        }
+    }
+
+    refined_indices = best_indices;
+    return refined;
+}
+
+// Picks the index of each texel with the lowest error against the quantized endpoints of its
+// region into `refined_indices`, rather than projecting it onto the line between them. The
+// anchor texels are limited to the first half of the indices, which the endpoints were already
+// swapped for.
+fn compute_indices_p2(
+    pattern_index: u32,
+    p0_endpoint0_unq: vec3<f32>,
+    p0_endpoint1_unq: vec3<f32>,
+    p1_endpoint0_unq: vec3<f32>,
+    p1_endpoint1_unq: vec3<f32>,
+) -> f32 {
+    let fixup_id = pattern_fixup_id(pattern_index);
+    var msle = 0.0;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let p0 = pattern(pattern_index, i) == 0u;
+        let endpoint0_unq = select(p1_endpoint0_unq, p0_endpoint0_unq, p0);
+        let endpoint1_unq = select(p1_endpoint1_unq, p0_endpoint1_unq, p0);
+
+        let index_num = select(8u, 4u, i == 0u || i == fixup_id);
+        var best_msle = 3.4e38;
+        var best_index = 0u;
+
+        for (
+            var index_candidate: u32 = 0u;
+            index_candidate < index_num;
+            index_candidate = index_candidate + 1u
+        ) {
+            let weight = floor((f32(index_candidate) * 64.0) / 7.0 + 0.5);
+            let texel_msle =
+                calc_msle(texels[i], finish_unquantize(endpoint0_unq, endpoint1_unq, weight));
+            if (texel_msle < best_msle) {
+                best_msle = texel_msle;
+                best_index = index_candidate;
+            }
+        }
+
+        refined_indices[i] = best_index;
+        msle = msle + best_msle;
+    }
+
+    return msle;
+}
+
+fn encode_p1(refinement_passes: u32) {
+    // compute endpoints (min/max RGB bbox)
+    var block_min = texels[0];
+    var block_max = texels[0];
+    for (var i: u32 = 1u; i < 16u; i = i + 1u) {
+        block_min = min(block_min, texels[i]);
+        block_max = max(block_max, texels[i]);
+    }
+
+    let inset = inset_color_bbox_p1(block_min, block_max);
+    let optimized = optimize_endpoints_p1(inset.min, inset.max);
+    block_min = optimized.min;
+    block_max = optimized.max;
+
+    var block_dir = block_max - block_min;
+    block_dir = block_dir / (block_dir.x + block_dir.y + block_dir.z);
+
+    // Truncated like the endpoints written to the block, so the MSLE below is that of the block.
+    var endpoint0 = floor(quantize10(block_min));
+    var endpoint1 = floor(quantize10(block_max));
+    var end_point0_pos = f32_to_f16(dot(block_min, block_dir));
+    var end_point1_pos = f32_to_f16(dot(block_max, block_dir));
+
+    // check if endpoint swap is required
+    let fixup_texel_pos = f32_to_f16(dot(texels[0], block_dir));
+    let fixup_index = compute_index4(fixup_texel_pos, end_point0_pos, end_point1_pos);
+    if (fixup_index > 7u) {
+        let tmp_pos = end_point0_pos;
+        end_point0_pos = end_point1_pos;
+        end_point1_pos = tmp_pos;
+
+        let tmp = endpoint0;
+        endpoint0 = endpoint1;
+        endpoint1 = tmp;
+
+        let tmp_block = block_min;
+        block_min = block_max;
+        block_max = tmp_block;
+    }
+
+    // compute indices
+    var indices: array<u32, 16>;
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let texel_pos = f32_to_f16(dot(texels[i], block_dir));
+        indices[i] = compute_index4(texel_pos, end_point0_pos, end_point1_pos);
+    }
+
+    // compute compression error (MSLE)
+    let endpoint0_unq = unquantize10(endpoint0);
+    let endpoint1_unq = unquantize10(endpoint1);
+    var msle = 0.0;
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let weight = floor((f32(indices[i]) * 64.0) / 15.0 + 0.5);
+        let texel_unc = finish_unquantize(endpoint0_unq, endpoint1_unq, weight);
+
+        msle = msle + calc_msle(texels[i], texel_unc);
+    }
+
+    if (refinement_passes > 0u) {
+        let refined = refine_p1(refinement_passes, block_min, block_max, endpoint0, endpoint1);
+        endpoint0 = refined.endpoint0;
+        endpoint1 = refined.endpoint1;
+        indices = refined_indices;
+        msle = refined.msle;
+    }
+
+    // encode block for mode 11
+    block_msle = msle;
+
+    let e0 = vec3<u32>(endpoint0);
+    let e1 = vec3<u32>(endpoint1);
+
+    // endpoints
+    var x = 0x03u;
+    x = x | (e0.x << 5u);
+    x = x | (e0.y << 15u);
+    x = x | (e0.z << 25u);
+    var y = e0.z >> 7u;
+    y = y | (e1.x << 3u);
+    y = y | (e1.y << 13u);
+    y = y | (e1.z << 23u);
+    var z = e1.z >> 9u;
+
+    // indices
+    z = z | (indices[0] << 1u);
+    z = z | (indices[1] << 4u);
+    z = z | (indices[2] << 8u);
+    z = z | (indices[3] << 12u);
+    z = z | (indices[4] << 16u);
+    z = z | (indices[5] << 20u);
+    z = z | (indices[6] << 24u);
+    z = z | (indices[7] << 28u);
+    var w = indices[8] << 0u;
+    w = w | (indices[9] << 4u);
+    w = w | (indices[10] << 8u);
+    w = w | (indices[11] << 12u);
+    w = w | (indices[12] << 16u);
+    w = w | (indices[13] << 20u);
+    w = w | (indices[14] << 24u);
+    w = w | (indices[15] << 28u);
+
+    encoded_block = vec4<u32>(x, y, z, w);
+}
+
+fn dist_to_line_sq(point_on_line: vec3<f32>, line_direction: vec3<f32>, point: vec3<f32>) -> f32 {
+    let w = point - point_on_line;
+    let x = w - dot(w, line_direction) * line_direction;
+    return dot(x, x);
+}
+
+// Evaluate how good is given P2 pattern for encoding current block
+fn evaluate_p2_pattern(pattern_index: u32) -> f32 {
+    var p0_block_min = vec3<f32>(HALF_MAX);
+    var p0_block_max = vec3<f32>(0.0);
+    var p1_block_min = vec3<f32>(HALF_MAX);
+    var p1_block_max = vec3<f32>(0.0);
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        if (pattern(pattern_index, i) == 0u) {
+            p0_block_min = min(p0_block_min, texels[i]);
+            p0_block_max = max(p0_block_max, texels[i]);
+        } else {
+            p1_block_min = min(p1_block_min, texels[i]);
+            p1_block_max = max(p1_block_max, texels[i]);
+        }
+    }
+
+    let p0_block_dir = normalize(p0_block_max - p0_block_min);
+    let p1_block_dir = normalize(p1_block_max - p1_block_min);
+
+    var sq_distance_from_line = 0.0;
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        if (pattern(pattern_index, i) == 0u) {
+            sq_distance_from_line = sq_distance_from_line
+                + dist_to_line_sq(p0_block_min, p0_block_dir, texels[i]);
+        } else {
+            sq_distance_from_line = sq_distance_from_line
+                + dist_to_line_sq(p1_block_min, p1_block_dir, texels[i]);
+        }
+    }
+
+    return sq_distance_from_line;
+}
+
+// Encodes a P2 pattern with the given endpoints for each region, if that lowers the error. Leaves
+// the indices of the endpoints as they were passed in, before any swap, in `fit_indices`.
+fn encode_p2_endpoints(pattern_index: u32, p0: Endpoints, p1: Endpoints, refine_indices: bool) {
+    var p0_block_min = p0.min;
+    var p0_block_max = p0.max;
+    var p1_block_min = p1.min;
+    var p1_block_max = p1.max;
+
+    var p0_block_dir = p0_block_max - p0_block_min;
+    var p1_block_dir = p1_block_max - p1_block_min;
+    p0_block_dir = p0_block_dir / (p0_block_dir.x + p0_block_dir.y + p0_block_dir.z);
+    p1_block_dir = p1_block_dir / (p1_block_dir.x + p1_block_dir.y + p1_block_dir.z);
+
+    var p0_endpoint0_pos = f32_to_f16(dot(p0_block_min, p0_block_dir));
+    var p0_endpoint1_pos = f32_to_f16(dot(p0_block_max, p0_block_dir));
+    var p1_endpoint0_pos = f32_to_f16(dot(p1_block_min, p1_block_dir));
+    var p1_endpoint1_pos = f32_to_f16(dot(p1_block_max, p1_block_dir));
+
+    let fixup_id = pattern_fixup_id(pattern_index);
+    let p0_fixup_texel_pos = f32_to_f16(dot(texels[0], p0_block_dir));
+    let p1_fixup_texel_pos = f32_to_f16(dot(texels[fixup_id], p1_block_dir));
+    let p0_fixup_index = compute_index3(p0_fixup_texel_pos, p0_endpoint0_pos, p0_endpoint1_pos);
+    let p1_fixup_index = compute_index3(p1_fixup_texel_pos, p1_endpoint0_pos, p1_endpoint1_pos);
+    let p0_swapped = p0_fixup_index > 3u;
+    let p1_swapped = p1_fixup_index > 3u;
+    if (p0_swapped) {
+        let tmp_pos = p0_endpoint0_pos;
+        p0_endpoint0_pos = p0_endpoint1_pos;
+        p0_endpoint1_pos = tmp_pos;
+
+        let tmp = p0_block_min;
+        p0_block_min = p0_block_max;
+        p0_block_max = tmp;
+    }
+    if (p1_swapped) {
+        let tmp_pos = p1_endpoint0_pos;
+        p1_endpoint0_pos = p1_endpoint1_pos;
+        p1_endpoint1_pos = tmp_pos;
+
+        let tmp = p1_block_min;
+        p1_block_min = p1_block_max;
+        p1_block_max = tmp;
+    }
+
+    var indices: array<u32, 16>;
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let p0_texel_pos = f32_to_f16(dot(texels[i], p0_block_dir));
+        let p1_texel_pos = f32_to_f16(dot(texels[i], p1_block_dir));
+        let p0_index = compute_index3(p0_texel_pos, p0_endpoint0_pos, p0_endpoint1_pos);
+        let p1_index = compute_index3(p1_texel_pos, p1_endpoint0_pos, p1_endpoint1_pos);
+
+        indices[i] = select(p1_index, p0_index, pattern(pattern_index, i) == 0u);
+    }
+
+    let endpoint760 = floor(quantize7(p0_block_min));
+    var endpoint761 = floor(quantize7(p0_block_max));
+    var endpoint762 = floor(quantize7(p1_block_min));
+    var endpoint763 = floor(quantize7(p1_block_max));
+
+    let endpoint950 = floor(quantize9(p0_block_min));
+    var endpoint951 = floor(quantize9(p0_block_max));
+    var endpoint952 = floor(quantize9(p1_block_min));
+    var endpoint953 = floor(quantize9(p1_block_max));
+
+    let max_val76 = vec3<f32>(f32(0x1F));
+    endpoint761 = clamp(endpoint761 - endpoint760, -max_val76, max_val76);
+    endpoint762 = clamp(endpoint762 - endpoint760, -max_val76, max_val76);
+    endpoint763 = clamp(endpoint763 - endpoint760, -max_val76, max_val76);
+
+    let max_val95 = vec3<f32>(f32(0xF));
+    endpoint951 = clamp(endpoint951 - endpoint950, -max_val95, max_val95);
+    endpoint952 = clamp(endpoint952 - endpoint950, -max_val95, max_val95);
+    endpoint953 = clamp(endpoint953 - endpoint950, -max_val95, max_val95);
+
+    let endpoint760_unq = unquantize7(endpoint760);
+    let endpoint761_unq = unquantize7(endpoint760 + endpoint761);
+    let endpoint762_unq = unquantize7(endpoint760 + endpoint762);
+    let endpoint763_unq = unquantize7(endpoint760 + endpoint763);
+    let endpoint950_unq = unquantize9(endpoint950);
+    let endpoint951_unq = unquantize9(endpoint950 + endpoint951);
+    let endpoint952_unq = unquantize9(endpoint950 + endpoint952);
+    let endpoint953_unq = unquantize9(endpoint950 + endpoint953);
+
+    var msle76 = 0.0;
+    var msle95 = 0.0;
+    if (refine_indices) {
+        msle76 = compute_indices_p2(
+            pattern_index,
+            endpoint760_unq,
+            endpoint761_unq,
+            endpoint762_unq,
+            endpoint763_unq
+        );
+        let indices76 = refined_indices;
+        msle95 = compute_indices_p2(
+            pattern_index,
+            endpoint950_unq,
+            endpoint951_unq,
+            endpoint952_unq,
+            endpoint953_unq
+        );
+
+        if (msle76 <= msle95) {
+            indices = indices76;
+        } else {
+            indices = refined_indices;
+        }
+    } else {
+        for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+            let p0 = pattern(pattern_index, i) == 0u;
+
+            let tmp760_unq = select(endpoint762_unq, endpoint760_unq, p0);
+            let tmp761_unq = select(endpoint763_unq, endpoint761_unq, p0);
+            let tmp950_unq = select(endpoint952_unq, endpoint950_unq, p0);
+            let tmp951_unq = select(endpoint953_unq, endpoint951_unq, p0);
+
+            let weight = floor((f32(indices[i]) * 64.0) / 7.0 + 0.5);
+            let texel_unc76 = finish_unquantize(tmp760_unq, tmp761_unq, weight);
+            let texel_unc95 = finish_unquantize(tmp950_unq, tmp951_unq, weight);
+
+            msle76 = msle76 + calc_msle(texels[i], texel_unc76);
+            msle95 = msle95 + calc_msle(texels[i], texel_unc95);
+        }
+    }
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let swapped = select(p1_swapped, p0_swapped, pattern(pattern_index, i) == 0u);
+        fit_indices[i] = select(indices[i], 7u - indices[i], swapped);
+    }
+
+    endpoint761 = sign_extend(endpoint761, 0x1Fu, 0x20u);
+    endpoint762 = sign_extend(endpoint762, 0x1Fu, 0x20u);
+    endpoint763 = sign_extend(endpoint763, 0x1Fu, 0x20u);
+
+    endpoint951 = sign_extend(endpoint951, 0xFu, 0x10u);
+    endpoint952 = sign_extend(endpoint952, 0xFu, 0x10u);
+    endpoint953 = sign_extend(endpoint953, 0xFu, 0x10u);
+
+    // encode block
+    let p2_msle = min(msle76, msle95);
+    if (p2_msle < block_msle) {
+        block_msle = p2_msle;
+
+        var x: u32;
+        var y: u32;
+        var z: u32;
+        var w = 0u;
+
+        if (p2_msle == msle76) {
+            // 7.6
+            let e0 = vec3<u32>(endpoint760);
+            let e1 = vec3<u32>(endpoint761);
+            let e2 = vec3<u32>(endpoint762);
+            let e3 = vec3<u32>(endpoint763);
+
+            x = 0x1u;
+            x = x | ((e2.y & 0x20u) >> 3u);
+            x = x | ((e3.y & 0x10u) >> 1u);
+            x = x | ((e3.y & 0x20u) >> 1u);
+            x = x | (e0.x << 5u);
+            x = x | ((e3.z & 0x01u) << 12u);
+            x = x | ((e3.z & 0x02u) << 12u);
+            x = x | ((e2.z & 0x10u) << 10u);
+            x = x | (e0.y << 15u);
+            x = x | ((e2.z & 0x20u) << 17u);
+            x = x | ((e3.z & 0x04u) << 21u);
+            x = x | ((e2.y & 0x10u) << 20u);
+            x = x | (e0.z << 25u);
+            y = (e3.z & 0x08u) >> 3u;
+            y = y | ((e3.z & 0x20u) >> 4u);
+            y = y | ((e3.z & 0x10u) >> 2u);
+            y = y | (e1.x << 3u);
+            y = y | ((e2.y & 0x0Fu) << 9u);
+            y = y | (e1.y << 13u);
+            y = y | ((e3.y & 0x0Fu) << 19u);
+            y = y | (e1.z << 23u);
+            y = y | ((e2.z & 0x07u) << 29u);
+            z = (e2.z & 0x08u) >> 3u;
+            z = z | (e2.x << 1u);
+            z = z | (e3.x << 7u);
+        } else {
+            // 9.5
+            let e0 = vec3<u32>(endpoint950);
+            let e1 = vec3<u32>(endpoint951);
+            let e2 = vec3<u32>(endpoint952);
+            let e3 = vec3<u32>(endpoint953);
+
+            x = 0xEu;
+            x = x | (e0.x << 5u);
+            x = x | ((e2.z & 0x10u) << 10u);
+            x = x | (e0.y << 15u);
+            x = x | ((e2.y & 0x10u) << 20u);
+            x = x | (e0.z << 25u);
+            y = e0.z >> 7u;
+            y = y | ((e3.z & 0x10u) >> 2u);
+            y = y | (e1.x << 3u);
+            y = y | ((e3.y & 0x10u) << 4u);
+            y = y | ((e2.y & 0x0Fu) << 9u);
+            y = y | (e1.y << 13u);
+            y = y | ((e3.z & 0x01u) << 18u);
+            y = y | ((e3.y & 0x0Fu) << 19u);
+            y = y | (e1.z << 23u);
+            y = y | ((e3.z & 0x02u) << 27u);
+            y = y | (e2.z << 29u);
+            z = (e2.z & 0x08u) >> 3u;
+            z = z | (e2.x << 1u);
+            z = z | ((e3.z & 0x04u) << 4u);
+            z = z | (e3.x << 7u);
+            z = z | ((e3.z & 0x08u) << 9u);
+        }
+
+        z = z | (pattern_index << 13u);
+        let block_fixup_id = pattern_fixup_id(pattern_index);
+        if (block_fixup_id == 15u) {
+            z = z | (indices[0] << 18u);
+            z = z | (indices[1] << 20u);
+            z = z | (indices[2] << 23u);
+            z = z | (indices[3] << 26u);
+            z = z | (indices[4] << 29u);
+            w = w | (indices[5] << 0u);
+            w = w | (indices[6] << 3u);
+            w = w | (indices[7] << 6u);
+            w = w | (indices[8] << 9u);
+            w = w | (indices[9] << 12u);
+            w = w | (indices[10] << 15u);
+            w = w | (indices[11] << 18u);
+            w = w | (indices[12] << 21u);
+            w = w | (indices[13] << 24u);
+            w = w | (indices[14] << 27u);
+            w = w | (indices[15] << 30u);
+        } else if (block_fixup_id == 2u) {
+            z = z | (indices[0] << 18u);
+            z = z | (indices[1] << 20u);
+            z = z | (indices[2] << 23u);
+            z = z | (indices[3] << 25u);
+            z = z | (indices[4] << 28u);
+            z = z | (indices[5] << 31u);
+            w = w | (indices[5] >> 1u);
+            w = w | (indices[6] << 2u);
+            w = w | (indices[7] << 5u);
+            w = w | (indices[8] << 8u);
+            w = w | (indices[9] << 11u);
+            w = w | (indices[10] << 14u);
+            w = w | (indices[11] << 17u);
+            w = w | (indices[12] << 20u);
+            w = w | (indices[13] << 23u);
+            w = w | (indices[14] << 26u);
+            w = w | (indices[15] << 29u);
+        } else {
+            z = z | (indices[0] << 18u);
+            z = z | (indices[1] << 20u);
+            z = z | (indices[2] << 23u);
+            z = z | (indices[3] << 26u);
+            z = z | (indices[4] << 29u);
+            w = w | (indices[5] << 0u);
+            w = w | (indices[6] << 3u);
+            w = w | (indices[7] << 6u);
+            w = w | (indices[8] << 9u);
+            w = w | (indices[9] << 11u);
+            w = w | (indices[10] << 14u);
+            w = w | (indices[11] << 17u);
+            w = w | (indices[12] << 20u);
+            w = w | (indices[13] << 23u);
+            w = w | (indices[14] << 26u);
+            w = w | (indices[15] << 29u);
+        }
+
+        encoded_block = vec4<u32>(x, y, z, w);
+    }
+}
+
+fn encode_p2_pattern(pattern_index: u32, optimize_endpoints: bool, refinement_passes: u32) {
+    var p0_block_min = vec3<f32>(HALF_MAX);
+    var p0_block_max = vec3<f32>(0.0);
+    var p1_block_min = vec3<f32>(HALF_MAX);
+    var p1_block_max = vec3<f32>(0.0);
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        if (pattern(pattern_index, i) == 0u) {
+            p0_block_min = min(p0_block_min, texels[i]);
+            p0_block_max = max(p0_block_max, texels[i]);
+        } else {
+            p1_block_min = min(p1_block_min, texels[i]);
+            p1_block_max = max(p1_block_max, texels[i]);
+        }
+    }
+
+    if (optimize_endpoints) {
+        let p0_inset = inset_color_bbox_p2(pattern_index, 0u, p0_block_min, p0_block_max);
+        let p1_inset = inset_color_bbox_p2(pattern_index, 1u, p1_block_min, p1_block_max);
+        let p0_optimized = optimize_endpoints_p2(pattern_index, 0u, p0_inset.min, p0_inset.max);
+        let p1_optimized = optimize_endpoints_p2(pattern_index, 1u, p1_inset.min, p1_inset.max);
+        p0_block_min = p0_optimized.min;
+        p0_block_max = p0_optimized.max;
+        p1_block_min = p1_optimized.min;
+        p1_block_max = p1_optimized.max;
+    }
+
+    encode_p2_endpoints(
+        pattern_index,
+        Endpoints(p0_block_min, p0_block_max),
+        Endpoints(p1_block_min, p1_block_max),
+        refinement_passes > 0u
+    );
+
+    // Alternate between refitting the endpoints to the indices and picking the indices with the
+    // lowest error for the new endpoints, keeping whichever encoding is best.
+    for (var refinement: u32 = 0u; refinement < refinement_passes; refinement = refinement + 1u) {
+        let p0_fitted = fit_endpoints_p2(pattern_index, 0u, p0_block_min, p0_block_max);
+        let p1_fitted = fit_endpoints_p2(pattern_index, 1u, p1_block_min, p1_block_max);
+        p0_block_min = p0_fitted.min;
+        p0_block_max = p0_fitted.max;
+        p1_block_min = p1_fitted.min;
+        p1_block_max = p1_fitted.max;
+
+        encode_p2_endpoints(pattern_index, p0_fitted, p1_fitted, true);
+    }
+}
+
+// Encodes `texels` into `encoded_block`, trying the two-region modes too if `flags` asks for them.
+fn encode_block(flags: u32) {
+    encoded_block = vec4<u32>(0u);
+    block_msle = 0.0;
+
+    encode_p1(refinement_passes_of(flags));
+
+    if ((flags & FLAG_ENCODE_P2) != 0u) {
+        // First score how well each pattern fits the block
+        var scores: array<f32, 32>;
+        for (
+            var pattern_index: u32 = 0u;
+            pattern_index < PATTERN_NUM;
+            pattern_index = pattern_index + 1u
+        ) {
+            scores[pattern_index] = evaluate_p2_pattern(pattern_index);
+        }
+
+        // Then encode the best ones, keeping whichever has the lowest error. Ties go to the
+        // lower pattern.
+        let candidates = ((flags >> FLAGS_PARTITIONS_SHIFT) & FLAGS_PARTITIONS_MASK) + 1u;
+        for (var candidate: u32 = 0u; candidate < candidates; candidate = candidate + 1u) {
+            var best_score = scores[0];
+            var best_pattern = 0u;
+
+            for (
+                var pattern_index: u32 = 1u;
+                pattern_index < PATTERN_NUM;
+                pattern_index = pattern_index + 1u
+            ) {
+                if (scores[pattern_index] < best_score) {
+                    best_pattern = pattern_index;
+                    best_score = scores[pattern_index];
+                }
+            }
+
+            encode_p2_pattern(best_pattern, false, refinement_passes_of(flags));
+            // The optimized endpoints are only kept where they lower the error.
+            if ((flags & FLAG_OPTIMIZE_ENDPOINTS_P2) != 0u) {
+                encode_p2_pattern(best_pattern, true, refinement_passes_of(flags));
+            }
+            // Larger than any score, so that it isn't picked again.
+            scores[best_pattern] = 3.4e38;
+        }
+    }
+}
+
+// Encodes `texels` with the 32 threads of a workgroup, which all need to call it for the same
+// block. Each thread fully encodes one two-region partition, and the first also the
+// single-region mode, rather than only encoding the best scoring partition like
+// `encode_block`. The candidate with the lowest error ends up in `encoded_block` of the first
+// thread.
+fn encode_block_cooperatively(thread: u32, flags: u32) {
+    encoded_block = vec4<u32>(0u);
+    // Larger than any error, so that the thread's partition is always kept.
+    block_msle = 3.4e38;
+
+    if (thread == 0u) {
+        encode_p1(refinement_passes_of(flags));
+    }
+
+    if ((flags & FLAG_ENCODE_P2) != 0u) {
+        encode_p2_pattern(thread, false, refinement_passes_of(flags));
+
+        if ((flags & FLAG_OPTIMIZE_ENDPOINTS_P2) != 0u) {
+            encode_p2_pattern(thread, true, refinement_passes_of(flags));
+        }
+    }
+
+    candidate_msle[thread] = block_msle;
+    candidate_blocks[thread] = encoded_block;
+
+    // Halves the candidates until the best is left in the first. Ties go to the lower thread,
+    // so that the result doesn't depend on scheduling.
+    for (var stride: u32 = 16u; stride > 0u; stride = stride / 2u) {
+        workgroupBarrier();
+
+        if (thread < stride && candidate_msle[thread + stride] < candidate_msle[thread]) {
+            candidate_msle[thread] = candidate_msle[thread + stride];
+            candidate_blocks[thread] = candidate_blocks[thread + stride];
+        }
+    }
+
+    workgroupBarrier();
+
+    encoded_block = candidate_blocks[0];
+    block_msle = candidate_msle[0];
+}
+
+// Clamps the texels to what an unsigned half can represent, replacing NaNs with zero or, with
+// FLAG_NAN_TO_AVERAGE, the average of the rest of the block. Returns how many texels changed.
+fn sanitize_texels(flags: u32) -> u32 {
+    var corrected = 0u;
+    var nan_mask = vec3<u32>(0u);
+    var sum = vec3<f32>(0.0);
+    var count = vec3<f32>(0.0);
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        let magnitude = bitcast<vec3<u32>>(texels[i]) & vec3<u32>(0x7FFFFFFFu);
+        let is_nan = magnitude > vec3<u32>(0x7F800000u);
+        let texel = clamp(
+            select(texels[i], vec3<f32>(0.0), is_nan),
+            vec3<f32>(0.0),
+            vec3<f32>(HALF_MAX)
+        );
+
+        if (any(is_nan) || any(texel != texels[i])) {
+            corrected = corrected + 1u;
+        }
+        nan_mask = nan_mask | select(vec3<u32>(0u), vec3<u32>(1u << i), is_nan);
+        sum = sum + select(texel, vec3<f32>(0.0), is_nan);
+        count = count + select(vec3<f32>(1.0), vec3<f32>(0.0), is_nan);
+        texels[i] = texel;
+    }
+
+    if ((flags & FLAG_NAN_TO_AVERAGE) != 0u) {
+        let average = sum / max(count, vec3<f32>(1.0));
+
+        for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+            let was_nan = ((nan_mask >> vec3<u32>(i)) & vec3<u32>(1u)) != vec3<u32>(0u);
+            texels[i] = select(texels[i], average, was_nan);
+        }
+    }
+
+    return corrected;
+}
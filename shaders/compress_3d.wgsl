@@ -0,0 +1,114 @@
+// The 3D entry point of the compressor, appended to `bc6h.wgsl` and `count_corrected.wgsl`.
+
+// Covers `size_in_blocks` blocks from the `block_offset` row and slice of `mip_level`, and writes
+// them to `buffer` from `index_offset`.
+struct Constants {
+    size_in_blocks: vec3<u32>;
+    flags: u32;
+    block_offset: vec2<u32>;
+    index_offset: u32;
+    mip_level: u32;
+};
+
+struct Blocks {
+    blocks: array<vec4<u32>>;
+};
+
+[[group(0), binding(0)]]
+var source: texture_3d<f32>;
+[[group(0), binding(1)]]
+var point_sampler: sampler;
+[[group(0), binding(2)]]
+var<storage, read_write> buffer: Blocks;
+[[group(0), binding(3)]]
+var<uniform> constants: Constants;
+
+[[stage(compute), workgroup_size(4, 4, 4)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let block_coord = id;
+
+    if (all(block_coord < constants.size_in_blocks)) {
+        // Converted per component, as wgpu's GL backend turns a vector `u32` to `i32` bitcast in
+        // the SPIR-V generated from this into a scalar one.
+        let xy = vec2<i32>(
+            i32(block_coord.x * 4u),
+            i32((block_coord.y + constants.block_offset.x) * 4u)
+        );
+        let z = i32(block_coord.z + constants.block_offset.y);
+
+        for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+            let offset = vec2<i32>(i32(i % 4u), i32(i / 4u));
+            let texel = vec3<i32>(xy + offset, z);
+            texels[i] = textureLoad(source, texel, i32(constants.mip_level)).rgb;
+        }
+
+        if ((constants.flags & FLAG_SANITIZE) != 0u) {
+            let corrected = sanitize_texels(constants.flags);
+
+            if ((constants.flags & FLAG_COUNT_CORRECTED) != 0u && corrected > 0u) {
+                count_corrected(corrected);
+            }
+        }
+
+        encode_block(constants.flags);
+
+        let width = constants.size_in_blocks.x;
+        let height = constants.size_in_blocks.y;
+        let index = constants.index_offset + block_coord.x + block_coord.y * width
+            + block_coord.z * (width * height);
+        buffer.blocks[index] = encoded_block;
+    }
+}
+
+// The `Quality::High` entry point, with a workgroup for each block instead of a thread, see
+// `encode_block_cooperatively`.
+[[stage(compute), workgroup_size(32, 1, 1)]]
+fn main_cooperative(
+    [[builtin(workgroup_id)]] workgroup_id: vec3<u32>,
+    [[builtin(local_invocation_index)]] thread: u32,
+) {
+    let block_coord = workgroup_id;
+
+    // The same for the whole workgroup, so no thread is left waiting at a barrier.
+    if (any(block_coord >= constants.size_in_blocks)) {
+        return;
+    }
+
+    // Converted per component, like in `main`.
+    let xy = vec2<i32>(
+        i32(block_coord.x * 4u),
+        i32((block_coord.y + constants.block_offset.x) * 4u)
+    );
+    let z = i32(block_coord.z + constants.block_offset.y);
+
+    // Each texel is loaded once for the whole workgroup.
+    if (thread < 16u) {
+        let texel = vec3<i32>(xy + vec2<i32>(i32(thread % 4u), i32(thread / 4u)), z);
+        shared_texels[thread] = textureLoad(source, texel, i32(constants.mip_level)).rgb;
+    }
+
+    workgroupBarrier();
+
+    for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+        texels[i] = shared_texels[i];
+    }
+
+    // Every thread sanitizes its own copy the same way, but only the first counts.
+    if ((constants.flags & FLAG_SANITIZE) != 0u) {
+        let corrected = sanitize_texels(constants.flags);
+
+        if ((constants.flags & FLAG_COUNT_CORRECTED) != 0u && corrected > 0u && thread == 0u) {
+            count_corrected(corrected);
+        }
+    }
+
+    encode_block_cooperatively(thread, constants.flags);
+
+    if (thread == 0u) {
+        let width = constants.size_in_blocks.x;
+        let height = constants.size_in_blocks.y;
+        let index = constants.index_offset + block_coord.x + block_coord.y * width
+            + block_coord.z * (width * height);
+        buffer.blocks[index] = encoded_block;
+    }
+}
@@ -0,0 +1,97 @@
+// The batch entry point of the compressor, appended to `bc6h.wgsl` and `count_corrected.wgsl`.
+//
+// Each job's texels are in a layer of `source`, from its top left corner. The workgroups of a
+// dispatch are numbered row by row, `workgroups_per_row` to a row, and each job covers
+// `first_workgroup` up to the next job's, one workgroup for each 8x8 blocks.
+struct Constants {
+    job_count: u32;
+    flags: u32;
+    workgroups_per_row: u32;
+    workgroup_count: u32;
+};
+
+struct Job {
+    size_in_blocks: vec2<u32>;
+    first_workgroup: u32;
+    index_offset: u32;
+};
+
+struct Jobs {
+    jobs: array<Job>;
+};
+
+struct Blocks {
+    blocks: array<vec4<u32>>;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d_array<f32>;
+[[group(0), binding(2)]]
+var<storage, read_write> buffer: Blocks;
+[[group(0), binding(3)]]
+var<uniform> constants: Constants;
+[[group(0), binding(5)]]
+var<storage, read> jobs: Jobs;
+
+// The last job that starts at or before `workgroup_index`.
+fn find_job(workgroup_index: u32) -> u32 {
+    var low: u32 = 0u;
+    var high: u32 = constants.job_count - 1u;
+
+    loop {
+        if (low >= high) {
+            break;
+        }
+
+        let middle = (low + high + 1u) / 2u;
+
+        if (jobs.jobs[middle].first_workgroup <= workgroup_index) {
+            low = middle;
+        } else {
+            high = middle - 1u;
+        }
+    }
+
+    return low;
+}
+
+[[stage(compute), workgroup_size(8, 8, 1)]]
+fn main(
+    [[builtin(workgroup_id)]] workgroup_id: vec3<u32>,
+    [[builtin(local_invocation_id)]] local_id: vec3<u32>,
+) {
+    let workgroup_index = workgroup_id.x + workgroup_id.y * constants.workgroups_per_row;
+
+    if (workgroup_index >= constants.workgroup_count) {
+        return;
+    }
+
+    let job_index = find_job(workgroup_index);
+    let job = jobs.jobs[job_index];
+
+    let tiles_per_row = (job.size_in_blocks.x + 7u) / 8u;
+    let tile = workgroup_index - job.first_workgroup;
+    let block_coord = vec2<u32>(tile % tiles_per_row, tile / tiles_per_row) * 8u + local_id.xy;
+
+    if (all(block_coord < job.size_in_blocks)) {
+        let xy = vec2<i32>(block_coord * 4u);
+
+        for (var i: u32 = 0u; i < 16u; i = i + 1u) {
+            let offset = vec2<i32>(i32(i % 4u), i32(i / 4u));
+            texels[i] = textureLoad(source, xy + offset, i32(job_index), 0).rgb;
+        }
+
+        if ((constants.flags & FLAG_SANITIZE) != 0u) {
+            let corrected = sanitize_texels(constants.flags);
+
+            if ((constants.flags & FLAG_COUNT_CORRECTED) != 0u && corrected > 0u) {
+                count_corrected(corrected);
+            }
+        }
+
+        encode_block(constants.flags);
+
+        let index = job.index_offset + block_coord.x + block_coord.y * job.size_in_blocks.x;
+        buffer.blocks[index] = encoded_block;
+    }
+}
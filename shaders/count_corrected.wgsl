@@ -0,0 +1,15 @@
+// Counts the texels changed by `sanitize_texels`, inserted between `bc6h.wgsl` and the entry point
+// when the compressors are created from WGSL. wgpu can't parse atomics from SPIR-V, so
+// `shaders/spirv.rs` inserts a `count_corrected` that does nothing instead, and the compressors use
+// the WGSL shaders whenever they are passed a `CorrectedTexelCounter`.
+
+struct CorrectedTexels {
+    count: atomic<u32>;
+};
+
+[[group(0), binding(4)]]
+var<storage, read_write> corrected_texels: CorrectedTexels;
+
+fn count_corrected(corrected: u32) {
+    atomicAdd(&corrected_texels.count, corrected);
+}
@@ -0,0 +1,116 @@
+// Projects an equirectangular panorama onto the six faces of a cubemap, written as the slices of
+// a 3D texture in the +X, -X, +Y, -Y, +Z, -Z order so that it can be bound to `Compressor3D`.
+
+struct Params {
+    face_size: u32;
+    filter: u32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_2d<f32>;
+[[group(0), binding(1)]]
+var destination: texture_storage_3d<rgba32float, write>;
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+let PI: f32 = 3.14159265358979;
+
+// Matches `Filter` in `src/equirect.rs`.
+let FILTER_BICUBIC: u32 = 1u;
+
+// The direction through a point on a face, with `uv` from -1 to 1, following the Vulkan and
+// D3D cubemap conventions.
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    var direction: vec3<f32>;
+
+    switch (i32(face)) {
+        case 0: { direction = vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1: { direction = vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2: { direction = vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3: { direction = vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4: { direction = vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { direction = vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+
+    return normalize(direction);
+}
+
+// The centre of the panorama faces -Z and the top row faces +Y.
+fn equirect_uv(direction: vec3<f32>) -> vec2<f32> {
+    return vec2<f32>(
+        0.5 + atan2(direction.x, -direction.z) / (2.0 * PI),
+        acos(clamp(direction.y, -1.0, 1.0)) / PI
+    );
+}
+
+// Wraps around horizontally and clamps vertically.
+fn fetch(x: i32, y: i32, size: vec2<i32>) -> vec4<f32> {
+    let x = ((x % size.x) + size.x) % size.x;
+    let y = clamp(y, 0, size.y - 1);
+    return textureLoad(source, vec2<i32>(x, y), 0);
+}
+
+fn sample_bilinear(uv: vec2<f32>, size: vec2<i32>) -> vec4<f32> {
+    let position = uv * vec2<f32>(size) - 0.5;
+    let base = vec2<i32>(floor(position));
+    let t = fract(position);
+
+    let top = mix(fetch(base.x, base.y, size), fetch(base.x + 1, base.y, size), t.x);
+    let bottom = mix(fetch(base.x, base.y + 1, size), fetch(base.x + 1, base.y + 1, size), t.x);
+
+    return mix(top, bottom, t.y);
+}
+
+fn catmull_rom_weights(t: f32) -> vec4<f32> {
+    return vec4<f32>(
+        t * (-0.5 + t * (1.0 - 0.5 * t)),
+        1.0 + t * t * (-2.5 + 1.5 * t),
+        t * (0.5 + t * (2.0 - 1.5 * t)),
+        t * t * (-0.5 + 0.5 * t)
+    );
+}
+
+fn sample_bicubic(uv: vec2<f32>, size: vec2<i32>) -> vec4<f32> {
+    let position = uv * vec2<f32>(size) - 0.5;
+    let base = vec2<i32>(floor(position)) - 1;
+    let t = fract(position);
+
+    let weights_x = catmull_rom_weights(t.x);
+    let weights_y = catmull_rom_weights(t.y);
+
+    var sum = vec4<f32>(0.0);
+
+    for (var y: i32 = 0; y < 4; y = y + 1) {
+        var row = vec4<f32>(0.0);
+
+        for (var x: i32 = 0; x < 4; x = x + 1) {
+            row = row + fetch(base.x + x, base.y + y, size) * weights_x[x];
+        }
+
+        sum = sum + row * weights_y[y];
+    }
+
+    // Catmull-Rom overshoots around sharp edges, and BC6H can't store negative values.
+    return max(sum, vec4<f32>(0.0));
+}
+
+[[stage(compute), workgroup_size(8, 8, 1)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    if (id.x >= params.face_size || id.y >= params.face_size) {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / f32(params.face_size) * 2.0 - 1.0;
+    let source_uv = equirect_uv(face_direction(id.z, uv));
+    let size = textureDimensions(source);
+
+    var color: vec4<f32>;
+
+    if (params.filter == FILTER_BICUBIC) {
+        color = sample_bicubic(source_uv, size);
+    } else {
+        color = sample_bilinear(source_uv, size);
+    }
+
+    textureStore(destination, vec3<i32>(id), color);
+}
@@ -0,0 +1,150 @@
+// Prefilters a cubemap with the GGX distribution for a single roughness, using importance
+// sampling and the usual assumption that the view and reflection directions equal the normal.
+// Cubemaps are read and written as the slices of 3D textures in the +X, -X, +Y, -Y, +Z, -Z
+// order, the same as `Compressor3D` expects.
+
+struct Params {
+    face_size: u32;
+    sample_count: u32;
+    roughness: f32;
+};
+
+[[group(0), binding(0)]]
+var source: texture_3d<f32>;
+[[group(0), binding(1)]]
+var destination: texture_storage_3d<rgba32float, write>;
+[[group(0), binding(2)]]
+var<uniform> params: Params;
+
+let PI: f32 = 3.14159265358979;
+
+// The same as in `equirect_to_cubemap.wgsl`.
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    var direction: vec3<f32>;
+
+    switch (i32(face)) {
+        case 0: { direction = vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1: { direction = vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2: { direction = vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3: { direction = vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4: { direction = vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { direction = vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+
+    return normalize(direction);
+}
+
+fn fetch(x: i32, y: i32, face: i32, size: i32) -> vec4<f32> {
+    return textureLoad(source, vec3<i32>(clamp(x, 0, size - 1), clamp(y, 0, size - 1), face), 0);
+}
+
+// Bilinearly samples the face that a direction points at. The inverse of `face_direction`.
+fn sample_cubemap(direction: vec3<f32>) -> vec4<f32> {
+    let abs_direction = abs(direction);
+
+    var face: i32;
+    var major: f32;
+    var uv: vec2<f32>;
+
+    if (abs_direction.x >= abs_direction.y && abs_direction.x >= abs_direction.z) {
+        major = abs_direction.x;
+        if (direction.x > 0.0) {
+            face = 0;
+            uv = vec2<f32>(-direction.z, -direction.y);
+        } else {
+            face = 1;
+            uv = vec2<f32>(direction.z, -direction.y);
+        }
+    } else if (abs_direction.y >= abs_direction.z) {
+        major = abs_direction.y;
+        if (direction.y > 0.0) {
+            face = 2;
+            uv = vec2<f32>(direction.x, direction.z);
+        } else {
+            face = 3;
+            uv = vec2<f32>(direction.x, -direction.z);
+        }
+    } else {
+        major = abs_direction.z;
+        if (direction.z > 0.0) {
+            face = 4;
+            uv = vec2<f32>(direction.x, -direction.y);
+        } else {
+            face = 5;
+            uv = vec2<f32>(-direction.x, -direction.y);
+        }
+    }
+
+    let size = textureDimensions(source).x;
+    let position = (uv / major * 0.5 + 0.5) * f32(size) - 0.5;
+    let base = vec2<i32>(floor(position));
+    let t = fract(position);
+
+    let top = mix(fetch(base.x, base.y, face, size), fetch(base.x + 1, base.y, face, size), t.x);
+    let bottom = mix(
+        fetch(base.x, base.y + 1, face, size),
+        fetch(base.x + 1, base.y + 1, face, size),
+        t.x
+    );
+
+    return mix(top, bottom, t.y);
+}
+
+fn radical_inverse(bits: u32) -> f32 {
+    var bits = bits;
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return f32(bits) * 2.3283064365386963e-10;
+}
+
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), radical_inverse(i));
+}
+
+// A half vector distributed according to GGX around the normal.
+fn importance_sample_ggx(xi: vec2<f32>, normal: vec3<f32>, alpha: f32) -> vec3<f32> {
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+
+    let tangent_space = vec3<f32>(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+
+    var up = vec3<f32>(0.0, 0.0, 1.0);
+    if (abs(normal.z) >= 0.999) {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+
+    return tangent * tangent_space.x + bitangent * tangent_space.y + normal * tangent_space.z;
+}
+
+[[stage(compute), workgroup_size(8, 8, 1)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    if (id.x >= params.face_size || id.y >= params.face_size) {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / f32(params.face_size) * 2.0 - 1.0;
+    let normal = face_direction(id.z, uv);
+    let alpha = params.roughness * params.roughness;
+
+    var sum = vec3<f32>(0.0);
+    var weight = 0.0;
+
+    for (var i: u32 = 0u; i < params.sample_count; i = i + 1u) {
+        let half_vector = importance_sample_ggx(hammersley(i, params.sample_count), normal, alpha);
+        let light = 2.0 * dot(normal, half_vector) * half_vector - normal;
+        let n_dot_l = dot(normal, light);
+
+        if (n_dot_l > 0.0) {
+            sum = sum + sample_cubemap(light).rgb * n_dot_l;
+            weight = weight + n_dot_l;
+        }
+    }
+
+    textureStore(destination, vec3<i32>(id), vec4<f32>(sum / max(weight, 0.0001), 1.0));
+}
@@ -0,0 +1,54 @@
+// Turns an extent written on the GPU into the constants and dispatch size of a compressor, so
+// that the CPU never has to read it back. See `src/indirect.rs`.
+
+struct Extent {
+    width_in_blocks: u32;
+    height_in_blocks: u32;
+    depth: u32;
+};
+
+// The compressor's constants, bound to it as a uniform buffer, followed by the arguments of
+// `dispatch_indirect`.
+struct Arguments {
+    constants: array<u32, 8>;
+    dispatch: array<u32, 3>;
+};
+
+// `dimensions` is 2 or 3, for the constants of `compress_2d.wgsl` or `compress_3d.wgsl`.
+struct Parameters {
+    max_size: vec3<u32>;
+    dimensions: u32;
+    group_size: vec3<u32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> extent: Extent;
+[[group(0), binding(1)]]
+var<storage, read_write> arguments: Arguments;
+[[group(0), binding(2)]]
+var<uniform> parameters: Parameters;
+
+[[stage(compute), workgroup_size(1, 1, 1)]]
+fn main() {
+    // Clamped so that a bad extent can't write past the end of the blocks.
+    var size = min(
+        vec3<u32>(extent.width_in_blocks, extent.height_in_blocks, extent.depth),
+        parameters.max_size,
+    );
+
+    if (parameters.dimensions == 2u) {
+        size.z = 1u;
+    }
+
+    arguments.constants[0] = size.x;
+    arguments.constants[1] = size.y;
+
+    if (parameters.dimensions == 3u) {
+        arguments.constants[2] = size.z;
+    }
+
+    let groups = (size + parameters.group_size - vec3<u32>(1u)) / parameters.group_size;
+    arguments.dispatch[0] = groups.x;
+    arguments.dispatch[1] = groups.y;
+    arguments.dispatch[2] = groups.z;
+}
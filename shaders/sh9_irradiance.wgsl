@@ -0,0 +1,153 @@
+// Projects a cubemap onto the first 9 real spherical harmonics (bands 0 to 2). The cubemap is
+// read as the slices of a 3D texture in the +X, -X, +Y, -Y, +Z, -Z order, the same as
+// `Compressor3D` expects.
+//
+// `reduce_texels` sums the contributions of a 64x64 tile of one face per workgroup into
+// `partials`, then `reduce_partials` sums those in a single workgroup and writes the normalized
+// coefficients to `coefficients`.
+
+struct Coefficients {
+    values: array<vec4<f32>>;
+};
+
+[[group(0), binding(0)]]
+var source: texture_3d<f32>;
+[[group(0), binding(1)]]
+var<storage, read_write> partials: Coefficients;
+[[group(0), binding(2)]]
+var<storage, read_write> coefficients: Coefficients;
+
+let PI: f32 = 3.14159265358979;
+let THREADS: u32 = 64u;
+// Each thread covers 8x8 texels, spaced out so that neighbouring threads read neighbouring texels.
+let TEXELS_PER_THREAD: u32 = 8u;
+
+var<workgroup> shared_sums: array<vec4<f32>, 576>;
+
+// The same as in `equirect_to_cubemap.wgsl`.
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    var direction: vec3<f32>;
+
+    switch (i32(face)) {
+        case 0: { direction = vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1: { direction = vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2: { direction = vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3: { direction = vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4: { direction = vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { direction = vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+
+    return normalize(direction);
+}
+
+// Sums the 9 coefficients of each thread in the workgroup into the first thread's slot.
+fn reduce_shared(index: u32) {
+    for (var stride: u32 = THREADS / 2u; stride > 0u; stride = stride >> 1u) {
+        workgroupBarrier();
+
+        if (index < stride) {
+            for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+                shared_sums[index * 9u + k] =
+                    shared_sums[index * 9u + k] + shared_sums[(index + stride) * 9u + k];
+            }
+        }
+    }
+
+    workgroupBarrier();
+}
+
+[[stage(compute), workgroup_size(8, 8, 1)]]
+fn reduce_texels(
+    [[builtin(workgroup_id)]] group: vec3<u32>,
+    [[builtin(num_workgroups)]] group_count: vec3<u32>,
+    [[builtin(local_invocation_id)]] local: vec3<u32>,
+    [[builtin(local_invocation_index)]] index: u32,
+) {
+    let size = u32(textureDimensions(source).x);
+    let tile_size = 8u * TEXELS_PER_THREAD;
+
+    var sums: array<vec4<f32>, 9>;
+    for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+        sums[k] = vec4<f32>(0.0);
+    }
+
+    for (var ty: u32 = 0u; ty < TEXELS_PER_THREAD; ty = ty + 1u) {
+        for (var tx: u32 = 0u; tx < TEXELS_PER_THREAD; tx = tx + 1u) {
+            let x = group.x * tile_size + tx * 8u + local.x;
+            let y = group.y * tile_size + ty * 8u + local.y;
+
+            if (x < size && y < size) {
+                let uv = (vec2<f32>(f32(x), f32(y)) + 0.5) / f32(size) * 2.0 - 1.0;
+                let d = face_direction(group.z, uv);
+
+                // The solid angle that the texel covers.
+                let weight = 4.0 / (f32(size) * f32(size)) / pow(1.0 + dot(uv, uv), 1.5);
+
+                let color = textureLoad(source, vec3<i32>(i32(x), i32(y), i32(group.z)), 0).rgb
+                    * weight;
+
+                var basis: array<f32, 9>;
+                basis[0] = 0.282095;
+                basis[1] = 0.488603 * d.y;
+                basis[2] = 0.488603 * d.z;
+                basis[3] = 0.488603 * d.x;
+                basis[4] = 1.092548 * d.x * d.y;
+                basis[5] = 1.092548 * d.y * d.z;
+                basis[6] = 0.315392 * (3.0 * d.z * d.z - 1.0);
+                basis[7] = 1.092548 * d.x * d.z;
+                basis[8] = 0.546274 * (d.x * d.x - d.y * d.y);
+
+                for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+                    sums[k] = sums[k] + vec4<f32>(color * basis[k], 0.0);
+                }
+
+                // The total solid angle is used to normalize away the approximation error.
+                sums[0].w = sums[0].w + weight;
+            }
+        }
+    }
+
+    for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+        shared_sums[index * 9u + k] = sums[k];
+    }
+
+    reduce_shared(index);
+
+    if (index == 0u) {
+        let partial = group.x + (group.y + group.z * group_count.y) * group_count.x;
+
+        for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+            partials.values[partial * 9u + k] = shared_sums[k];
+        }
+    }
+}
+
+[[stage(compute), workgroup_size(64, 1, 1)]]
+fn reduce_partials([[builtin(local_invocation_index)]] index: u32) {
+    let partial_count = arrayLength(&partials.values) / 9u;
+
+    var sums: array<vec4<f32>, 9>;
+    for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+        sums[k] = vec4<f32>(0.0);
+    }
+
+    for (var partial: u32 = index; partial < partial_count; partial = partial + THREADS) {
+        for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+            sums[k] = sums[k] + partials.values[partial * 9u + k];
+        }
+    }
+
+    for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+        shared_sums[index * 9u + k] = sums[k];
+    }
+
+    reduce_shared(index);
+
+    if (index == 0u) {
+        let scale = 4.0 * PI / shared_sums[0].w;
+
+        for (var k: u32 = 0u; k < 9u; k = k + 1u) {
+            coefficients.values[k] = vec4<f32>(shared_sums[k].rgb * scale, 0.0);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Generates the SPIR-V in `shaders/compiled` from the WGSL shaders with naga. Included by
+//! `build.rs` with the `compile_shaders` feature, and by `tests/spirv.rs`, which checks that the
+//! checked-in files are up to date.
+//!
+//! wgpu's SPIR-V frontend can't parse atomics, so the variants are generated with a
+//! `count_corrected` that does nothing, and without the `Quality::High` entry point, which is only
+//! ever created from WGSL.
+
+const ENCODER: &str = include_str!("bc6h.wgsl");
+
+const COUNT_CORRECTED: &str = "fn count_corrected(corrected: u32) {}\n";
+
+const UNIFORM_CONSTANTS: &str = "[[group(0), binding(3)]]\nvar<uniform> constants: Constants;";
+const PUSH_CONSTANTS: &str = "var<push_constant> constants: Constants;";
+
+/// The file name of each variant, the entry point it is generated from and whether its constants
+/// are push constants. Quality isn't a variant, as the shader reads it from `Constants.flags`.
+pub const VARIANTS: &[(&str, &str, bool)] = &[
+    ("2d.comp.spv", include_str!("compress_2d.wgsl"), false),
+    ("3d.comp.spv", include_str!("compress_3d.wgsl"), false),
+    (
+        "2d_push_constants.comp.spv",
+        include_str!("compress_2d.wgsl"),
+        true,
+    ),
+    (
+        "3d_push_constants.comp.spv",
+        include_str!("compress_3d.wgsl"),
+        true,
+    ),
+];
+
+/// Generates the SPIR-V of the `main` entry point of `entry_point`, in the byte order wgpu's
+/// `include_spirv!` expects.
+pub fn compile(entry_point: &str, push_constants: bool) -> Result<Vec<u8>, String> {
+    let mut source = format!("{}\n{}\n{}", ENCODER, COUNT_CORRECTED, entry_point);
+
+    if push_constants {
+        source = source.replace(UNIFORM_CONSTANTS, PUSH_CONSTANTS);
+    }
+
+    let module =
+        naga::front::wgsl::parse_str(&source).map_err(|error| error.emit_to_string(&source))?;
+
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .map_err(|error| format!("{:?}", error))?;
+
+    // No debug info, so that the output doesn't depend on where the crate is built.
+    let options = naga::back::spv::Options {
+        flags: naga::back::spv::WriterFlags::empty(),
+        ..Default::default()
+    };
+    let pipeline_options = naga::back::spv::PipelineOptions {
+        shader_stage: naga::ShaderStage::Compute,
+        entry_point: "main".to_owned(),
+    };
+
+    let words = naga::back::spv::write_vec(&module, &info, &options, Some(&pipeline_options))
+        .map_err(|error| format!("{:?}", error))?;
+
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
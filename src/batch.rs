@@ -0,0 +1,441 @@
+//! Compressing many small textures, such as lightmap pages, in a single dispatch.
+
+use std::ops::Range;
+use wgpu::util::DeviceExt;
+
+use crate::{
+    compression_flags, dispatch_count, placeholder_counter, profiling, requirements, sanitization,
+    wgsl, Quality,
+};
+
+/// A region of a texture to compress as part of a batch.
+#[derive(Clone, Debug)]
+pub struct BatchJob<'a> {
+    /// Where to copy the texels from. The texture has to be
+    /// [`wgpu::TextureFormat::Rgba32Float`], with [`wgpu::TextureUsages::COPY_SRC`].
+    pub source: wgpu::ImageCopyTexture<'a>,
+    /// The size of the region, whose width and height have to be multiples of 4 and whose depth
+    /// has to be 1.
+    pub extent: wgpu::Extent3d,
+    /// Where the job's blocks start in the output buffer, in bytes. It has to be a multiple of 16.
+    pub output_offset: u64,
+}
+
+impl BatchJob<'_> {
+    fn size_in_blocks(&self) -> [u32; 2] {
+        [self.extent.width / 4, self.extent.height / 4]
+    }
+
+    // The bytes of the output buffer that the job's blocks are written to, from the last multiple
+    // of `alignment` before them so that they can be bound on their own.
+    fn output_range(&self, alignment: u64) -> Range<u64> {
+        let [width, height] = self.size_in_blocks();
+        let start = self.output_offset / alignment * alignment;
+        start..self.output_offset + width as u64 * height as u64 * 16
+    }
+}
+
+pub struct BatchParams<'a> {
+    pub bind_group_label: Option<&'a str>,
+    /// Anything but [`Quality::High`], which needs a workgroup for each block.
+    pub quality: Quality,
+    /// Records how long the compression takes on the GPU.
+    pub profiler: Option<&'a profiling::Profiler>,
+    pub sanitization: sanitization::Sanitization,
+    /// Counts the texels changed by `sanitization`.
+    pub corrected_texel_counter: Option<&'a sanitization::CorrectedTexelCounter>,
+}
+
+/// Compresses a list of [`BatchJob`]s with one indirect dispatch, instead of a compute pass,
+/// bind group and uniform buffer for each texture.
+///
+/// The jobs are copied into the layers of a 2D array texture, and a job table in a storage buffer
+/// maps each workgroup to its layer and blocks. Jobs beyond `max_texture_array_layers`, or whose
+/// blocks span more of the output buffer than `max_storage_buffer_binding_size`, go into further
+/// dispatches. It always uses the WGSL port of the shader, and needs
+/// [`wgpu::DownlevelFlags::INDIRECT_EXECUTION`].
+pub struct BatchCompressor {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    // Bound in place of a `CorrectedTexelCounter` when none is passed.
+    placeholder_counter: wgpu::Buffer,
+    push_constants: bool,
+}
+
+// The textures, buffers and bind group of up to `max_texture_array_layers` jobs.
+struct Dispatch {
+    bind_group: wgpu::BindGroup,
+    constants: [u32; 8],
+    indirect_buffer: wgpu::Buffer,
+}
+
+impl BatchCompressor {
+    /// Like [`new`](Self::new), but checks the device against the
+    /// [`DeviceRequirements`](requirements::DeviceRequirements) first, instead of failing when
+    /// creating the pipeline or dispatching.
+    pub fn try_new(device: &wgpu::Device) -> Result<Self, requirements::RequirementsError> {
+        requirements::DeviceRequirements::new(&requirements::Configuration {
+            compressed_textures: false,
+            push_constants: requirements::can_use_push_constants(device),
+        })
+        .check(device)?;
+
+        Ok(Self::new(device))
+    }
+
+    /// Uses push constants for the constants if the device has
+    /// [`wgpu::Features::PUSH_CONSTANTS`] and a large enough `max_push_constant_size`, or a
+    /// uniform buffer otherwise.
+    pub fn new(device: &wgpu::Device) -> Self {
+        let push_constants = requirements::can_use_push_constants(device);
+
+        let shader = device.create_shader_module(&wgsl::shader_module_descriptor(
+            "wgpu-bc6h-compression batch shader",
+            include_str!("../shaders/compress_batch.wgsl"),
+            push_constants,
+        ));
+
+        let storage_buffer = |binding, read_only, min_binding_size| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: std::num::NonZeroU64::new(min_binding_size),
+            },
+            count: None,
+        };
+
+        let mut layout_entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            storage_buffer(2, false, 0),
+            storage_buffer(4, false, 4),
+            storage_buffer(5, true, 0),
+        ];
+
+        if !push_constants {
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
+        }
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression batch bind group layout"),
+            entries: &layout_entries,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression batch pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: if push_constants {
+                &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::COMPUTE,
+                    range: 0..requirements::PUSH_CONSTANT_SIZE,
+                }]
+            } else {
+                &[]
+            },
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("wgpu-bc6h-compression batch pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            placeholder_counter: placeholder_counter(device),
+            push_constants,
+        }
+    }
+
+    /// Writes the blocks of each job to `buffer` from its `output_offset`, laid out as by
+    /// [`Compressor2D::compress_to_buffer`](crate::Compressor2D::compress_to_buffer).
+    ///
+    /// Panics if the blocks of a single job don't fit in a storage buffer binding.
+    pub fn compress_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &BatchParams,
+        jobs: &[BatchJob],
+        buffer: &wgpu::Buffer,
+    ) {
+        debug_assert_ne!(
+            params.quality,
+            Quality::High,
+            "a batch is dispatched one thread to a block"
+        );
+
+        if jobs.is_empty() {
+            return;
+        }
+
+        let limits = device.limits();
+        let max_layers = limits.max_texture_array_layers.max(1) as usize;
+        let max_binding_size = limits.max_storage_buffer_binding_size as u64;
+        let alignment = limits.min_storage_buffer_offset_alignment as u64;
+
+        let mut dispatches = Vec::new();
+        let mut first = 0;
+
+        while first < jobs.len() {
+            let mut output_range = jobs[first].output_range(alignment);
+            let mut end = first + 1;
+
+            assert!(
+                output_range.end - output_range.start <= max_binding_size,
+                "the blocks of a batch job don't fit in a storage buffer binding"
+            );
+
+            // Add jobs to the dispatch until the texture runs out of layers or the blocks of its
+            // jobs span more of the buffer than can be bound at once.
+            while end < jobs.len() && end - first < max_layers {
+                let job_range = jobs[end].output_range(alignment);
+                let merged =
+                    output_range.start.min(job_range.start)..output_range.end.max(job_range.end);
+
+                if merged.end - merged.start > max_binding_size {
+                    break;
+                }
+
+                output_range = merged;
+                end += 1;
+            }
+
+            dispatches.push(self.prepare(
+                device,
+                command_encoder,
+                params,
+                &jobs[first..end],
+                buffer,
+                output_range,
+            ));
+            first = end;
+        }
+
+        let blocks = jobs
+            .iter()
+            .map(|job| {
+                let [width, height] = job.size_in_blocks();
+                width as u64 * height as u64
+            })
+            .sum();
+
+        let timestamp = params
+            .profiler
+            .and_then(|profiler| profiler.begin_pass(command_encoder, blocks));
+
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            compute_pass.set_pipeline(&self.pipeline);
+
+            for dispatch in &dispatches {
+                compute_pass.set_bind_group(0, &dispatch.bind_group, &[]);
+                // Otherwise the constants are in the bind group.
+                if self.push_constants {
+                    compute_pass.set_push_constants(0, bytemuck::bytes_of(&dispatch.constants));
+                }
+                compute_pass.dispatch_indirect(&dispatch.indirect_buffer, 0);
+            }
+        }
+
+        if let (Some(profiler), Some(index)) = (params.profiler, timestamp) {
+            profiler.end_pass(command_encoder, index);
+        }
+    }
+
+    // Copies the jobs into the layers of a texture and builds their job table, with `output_range`
+    // of the buffer bound.
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &BatchParams,
+        jobs: &[BatchJob],
+        buffer: &wgpu::Buffer,
+        output_range: Range<u64>,
+    ) -> Dispatch {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("wgpu-bc6h-compression batch texels"),
+            size: wgpu::Extent3d {
+                width: jobs
+                    .iter()
+                    .map(|job| job.extent.width)
+                    .max()
+                    .unwrap()
+                    .max(4),
+                height: jobs
+                    .iter()
+                    .map(|job| job.extent.height)
+                    .max()
+                    .unwrap()
+                    .max(4),
+                // wgpu's GL backend creates textures with a single layer as 2D rather than 2D
+                // array textures, which the shader can't load from.
+                depth_or_array_layers: (jobs.len() as u32).max(2),
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        let mut job_table = Vec::with_capacity(jobs.len());
+        let mut workgroup_count = 0;
+
+        for (layer, job) in jobs.iter().enumerate() {
+            let [width, height] = job.size_in_blocks();
+            debug_assert_eq!(job.extent.width % 4, 0);
+            debug_assert_eq!(job.extent.height % 4, 0);
+            debug_assert_eq!(job.extent.depth_or_array_layers, 1);
+            debug_assert_eq!(job.output_offset % 16, 0);
+
+            command_encoder.copy_texture_to_texture(
+                job.source.clone(),
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                job.extent,
+            );
+
+            job_table.push([
+                width,
+                height,
+                workgroup_count,
+                ((job.output_offset - output_range.start) / 16) as u32,
+            ]);
+
+            workgroup_count += dispatch_count(width, 8) * dispatch_count(height, 8);
+        }
+
+        // Spreads the workgroups over rows when there are more than a dimension allows.
+        let workgroups_per_row = workgroup_count
+            .min(device.limits().max_compute_workgroups_per_dimension)
+            .max(1);
+
+        let constants = [
+            jobs.len() as u32,
+            params.flags(),
+            workgroups_per_row,
+            workgroup_count,
+            0,
+            0,
+            0,
+            0,
+        ];
+
+        let indirect_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wgpu-bc6h-compression batch dispatch"),
+            contents: bytemuck::cast_slice(&[
+                workgroups_per_row,
+                dispatch_count(workgroup_count, workgroups_per_row),
+                1,
+            ]),
+            usage: wgpu::BufferUsages::INDIRECT,
+        });
+
+        let job_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wgpu-bc6h-compression batch jobs"),
+            contents: bytemuck::cast_slice(&job_table),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let compute_constant_buffer = (!self.push_constants).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::bytes_of(&constants),
+                usage: wgpu::BufferUsages::UNIFORM,
+            })
+        });
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset: output_range.start,
+                    size: std::num::NonZeroU64::new(output_range.end - output_range.start),
+                }),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: params
+                    .corrected_texel_counter
+                    .map_or(&self.placeholder_counter, |counter| &counter.buffer)
+                    .as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: job_buffer.as_entire_binding(),
+            },
+        ];
+
+        if let Some(compute_constant_buffer) = &compute_constant_buffer {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 3,
+                resource: compute_constant_buffer.as_entire_binding(),
+            });
+        }
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: params.bind_group_label,
+            layout: &self.bind_group_layout,
+            entries: &entries,
+        });
+
+        Dispatch {
+            bind_group,
+            constants,
+            indirect_buffer,
+        }
+    }
+}
+
+impl BatchParams<'_> {
+    // The `Constants.flags` of the shader.
+    fn flags(&self) -> u32 {
+        compression_flags(
+            self.quality,
+            self.sanitization,
+            self.corrected_texel_counter.is_some(),
+        )
+    }
+}
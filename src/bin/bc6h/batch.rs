@@ -0,0 +1,316 @@
+//! Compressing every image in a directory, reusing one device for all of them.
+//!
+//! The work is split into three stages that run at the same time, so that the device is kept
+//! busy: a loader thread reads, hashes and decodes the inputs, the main thread uploads them and
+//! submits the compression work, and a writer thread reads the blocks back and writes the
+//! outputs. Inputs whose hash matches the cache manifest in the output directory are skipped.
+//! The manifest is rewritten after every output, so an interrupted run doesn't redo them.
+
+use crate::{
+    check_supercompression, gpu, input, load, output, to_signed, Args, Compressed, Result,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// The name of the cache manifest written to the output directory.
+const MANIFEST_NAME: &str = ".bc6h-cache";
+
+/// How many inputs each stage can get ahead of the next one.
+const PIPELINE_DEPTH: usize = 2;
+
+/// Hashes of the inputs and settings that produced each output, keyed by the input path
+/// relative to the input directory.
+type Manifest = BTreeMap<String, String>;
+
+struct Job {
+    key: String,
+    hash: String,
+    output_path: PathBuf,
+    compressed: Compressed,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    check_supercompression(args, args.format)?;
+
+    std::fs::create_dir_all(&args.output).map_err(|error| {
+        format!(
+            "failed to create the output directory '{}': {}",
+            args.output.display(),
+            error
+        )
+    })?;
+
+    let input_dir = args.input.canonicalize()?;
+    let output_dir = args.output.canonicalize()?;
+
+    if input_dir == output_dir {
+        return Err("the output directory must be different to the input directory".into());
+    }
+
+    let manifest_path = output_dir.join(MANIFEST_NAME);
+    let manifest = read_manifest(&manifest_path)?;
+
+    let mut inputs = Vec::new();
+    find_inputs(&input_dir, &output_dir, &mut inputs)?;
+    inputs.sort();
+
+    let inputs: Vec<_> = inputs
+        .into_iter()
+        .map(|path| {
+            let output_path = output_dir
+                .join(path.strip_prefix(&input_dir).unwrap())
+                .with_extension(args.format.extension());
+            (path, output_path)
+        })
+        .collect();
+
+    check_output_collisions(&input_dir, &inputs)?;
+
+    let gpu = gpu::Gpu::new()?;
+    let settings = settings_key(args);
+
+    let ((unchanged, load_failures), (written, write_failures)) = std::thread::scope(|scope| {
+        let (job_sender, job_receiver) = mpsc::sync_channel(PIPELINE_DEPTH);
+        let (submission_sender, submission_receiver) =
+            mpsc::sync_channel::<(Job, gpu::Submission)>(PIPELINE_DEPTH);
+
+        let (inputs, input_dir, manifest, manifest_path, settings, gpu) = (
+            &inputs,
+            &input_dir,
+            &manifest,
+            &manifest_path,
+            &settings,
+            &gpu,
+        );
+
+        let loader = scope.spawn(move || {
+            let mut unchanged = Manifest::new();
+            let mut failures = 0;
+
+            for (path, output_path) in inputs {
+                let key = relative_key(input_dir, path);
+
+                let hash = match hash_input(path, settings) {
+                    Ok(hash) => hash,
+                    Err(error) => {
+                        eprintln!("error: {}: {}", key, error);
+                        failures += 1;
+                        continue;
+                    }
+                };
+
+                if manifest.get(&key) == Some(&hash) && output_path.exists() {
+                    unchanged.insert(key, hash);
+                    continue;
+                }
+
+                match load(args, path) {
+                    Ok((compressed, levels)) => {
+                        let job = Job {
+                            key,
+                            hash,
+                            output_path: output_path.clone(),
+                            compressed,
+                        };
+
+                        if job_sender.send((job, levels)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(error) => {
+                        eprintln!("error: {}: {}", key, error);
+                        failures += 1;
+                    }
+                }
+            }
+
+            (unchanged, failures)
+        });
+
+        let writer = scope.spawn(move || {
+            let mut written = Manifest::new();
+            let mut failures = 0;
+
+            // The entries of outputs that haven't been reached yet still hold until they are.
+            let mut progress = manifest.clone();
+
+            for (mut job, submission) in submission_receiver {
+                let result = gpu.read(submission).and_then(|mut levels| {
+                    if args.signed {
+                        to_signed(&mut levels)?;
+                    }
+                    job.compressed.levels = levels;
+
+                    if let Some(parent) = job.output_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+
+                    output::write(
+                        &job.output_path,
+                        args.format,
+                        &job.compressed,
+                        args.zstd_level(),
+                    )
+                });
+
+                match result {
+                    Ok(()) => {
+                        println!("compressed {}", job.key);
+
+                        progress.insert(job.key.clone(), job.hash.clone());
+                        if let Err(error) = write_manifest(manifest_path, &progress) {
+                            eprintln!("error: {}", error);
+                        }
+
+                        written.insert(job.key, job.hash);
+                    }
+                    Err(error) => {
+                        eprintln!("error: {}: {}", job.key, error);
+                        failures += 1;
+                    }
+                }
+            }
+
+            (written, failures)
+        });
+
+        for (job, levels) in job_receiver {
+            let submission = gpu.submit(&levels, args.quality());
+
+            if submission_sender.send((job, submission)).is_err() {
+                break;
+            }
+        }
+
+        drop(submission_sender);
+
+        (loader.join().unwrap(), writer.join().unwrap())
+    });
+
+    println!(
+        "{} compressed, {} unchanged, {} failed",
+        written.len(),
+        unchanged.len(),
+        load_failures + write_failures
+    );
+
+    // Inputs that failed or no longer exist are left out, so they're retried next time.
+    let mut manifest = unchanged;
+    manifest.extend(written);
+    write_manifest(&manifest_path, &manifest)?;
+
+    match load_failures + write_failures {
+        0 => Ok(()),
+        failures => Err(format!("{} of {} inputs failed", failures, inputs.len()).into()),
+    }
+}
+
+fn relative_key(input_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(input_dir)
+        .unwrap()
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Inputs that only differ by their extension, such as `sky.exr` and `sky.hdr`, would be written
+/// to the same output. They are compared ignoring case, as on case-insensitive file systems.
+fn check_output_collisions(input_dir: &Path, inputs: &[(PathBuf, PathBuf)]) -> Result<()> {
+    let mut outputs = HashMap::new();
+
+    for (path, output_path) in inputs {
+        let output_key = output_path.to_string_lossy().to_lowercase();
+
+        if let Some(other) = outputs.insert(output_key, path) {
+            return Err(format!(
+                "'{}' and '{}' would both be written to '{}'",
+                relative_key(input_dir, other),
+                relative_key(input_dir, path),
+                output_path.display()
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Recursively find all the inputs with a supported extension, skipping the output directory in
+/// case it's nested inside the input directory.
+fn find_inputs(dir: &Path, output_dir: &Path, inputs: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|error| format!("failed to read '{}': {}", dir.display(), error))?;
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            if path != output_dir {
+                find_inputs(&path, output_dir, inputs)?;
+            }
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                input::EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            })
+        {
+            inputs.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything besides the input itself that affects the output, so that changing any of them
+/// invalidates the cache.
+fn settings_key(args: &Args) -> String {
+    format!(
+        "{} {:?} {:?} {} {:?} {} {:?} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        args.format,
+        args.quality(),
+        args.mips,
+        args.cubemap,
+        args.signed,
+        args.zstd_level(),
+        args.layer,
+    )
+}
+
+fn hash_input(path: &Path, settings: &str) -> Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(settings.as_bytes());
+    hasher.update(&std::fs::read(path)?);
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// The manifest has a line for each output, holding the hash and then the input path.
+fn read_manifest(path: &Path) -> Result<Manifest> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Manifest::new()),
+        Err(error) => return Err(format!("failed to read '{}': {}", path.display(), error).into()),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(hash, key)| (key.to_string(), hash.to_string()))
+        .collect())
+}
+
+/// Written to a temporary file first, so that an interrupted write can't leave a truncated
+/// manifest behind.
+fn write_manifest(path: &Path, manifest: &Manifest) -> Result<()> {
+    let contents: String = manifest
+        .iter()
+        .map(|(key, hash)| format!("{} {}\n", hash, key))
+        .collect();
+
+    let temporary_path = path.with_extension("tmp");
+
+    std::fs::write(&temporary_path, contents)
+        .and_then(|()| std::fs::rename(&temporary_path, path))
+        .map_err(|error| format!("failed to write '{}': {}", path.display(), error).into())
+}
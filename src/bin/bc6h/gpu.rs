@@ -0,0 +1,165 @@
+use crate::Result;
+use wgpu_bc6h_compression::{
+    requirements::{Configuration, DeviceRequirements},
+    sanitization::Sanitization,
+    CompressionParams, Compressor2D, Compressor3D, HdrImage, Quality,
+};
+
+pub struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    sampler: wgpu::Sampler,
+    compressor_2d: Compressor2D,
+    compressor_3d: Compressor3D,
+}
+
+impl Gpu {
+    pub fn new() -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or("no suitable graphics adapter was found")?;
+
+        let requirements = DeviceRequirements::new(&Configuration::for_adapter(&adapter));
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+
+                features: requirements.features,
+                limits: requirements.limits.using_resolution(adapter.limits()),
+            },
+            None,
+        ))
+        .map_err(|error| format!("failed to create a device: {}", error))?;
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let compressor_2d = Compressor2D::try_new(&device)?;
+        let compressor_3d = Compressor3D::try_new(&device)?;
+
+        Ok(Self {
+            device,
+            queue,
+            sampler,
+            compressor_2d,
+            compressor_3d,
+        })
+    }
+
+    /// Compress each mip level of an image in a single submission, padding the levels to a
+    /// whole number of blocks first. Images with a depth, including cubemaps, are compressed
+    /// slice by slice with the 3D compressor.
+    ///
+    /// The blocks aren't read back until [`Gpu::read`] is called, so more work can be submitted
+    /// in the meantime to keep the device busy.
+    pub fn submit(&self, levels: &[HdrImage], quality: Quality) -> Submission {
+        let mut command_encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        let buffers = levels
+            .iter()
+            .map(|level| self.encode(&mut command_encoder, level, quality))
+            .collect();
+
+        self.queue.submit(Some(command_encoder.finish()));
+
+        Submission { buffers }
+    }
+
+    /// Wait for a submission to finish and read back the blocks of each level.
+    pub fn read(&self, submission: Submission) -> Result<Vec<Vec<u8>>> {
+        let map_futures: Vec<_> = submission
+            .buffers
+            .iter()
+            .map(|buffer| buffer.slice(..).map_async(wgpu::MapMode::Read))
+            .collect();
+
+        self.device.poll(wgpu::Maintain::Wait);
+
+        submission
+            .buffers
+            .iter()
+            .zip(map_futures)
+            .map(|(buffer, map_future)| {
+                pollster::block_on(map_future)
+                    .map_err(|_| "failed to read the compressed blocks back from the device")?;
+
+                let blocks = buffer.slice(..).get_mapped_range().to_vec();
+                buffer.unmap();
+                Ok(blocks)
+            })
+            .collect()
+    }
+
+    fn encode(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        image: &HdrImage,
+        quality: Quality,
+    ) -> wgpu::Buffer {
+        let image = image.padded_to_blocks();
+        let extent = image.extent();
+
+        let texture_view = image
+            .create_texture(&self.device, &self.queue, Some("uncompressed texture"))
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        // BC6H blocks are 16 bytes for 16 texels.
+        let buffer_size =
+            extent.width as u64 * extent.height as u64 * extent.depth_or_array_layers as u64;
+
+        let target_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("compressed blocks"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let params = CompressionParams {
+            bind_group_label: None,
+            sampler: &self.sampler,
+            texture: &texture_view,
+            extent,
+            quality,
+            source_mip_level: 0,
+            source_base_layer: 0,
+            profiler: None,
+            sanitization: Sanitization::default(),
+            corrected_texel_counter: None,
+        };
+
+        if image.is_3d() {
+            self.compressor_3d.compress_to_buffer(
+                &self.device,
+                command_encoder,
+                &params,
+                &target_buffer,
+            );
+        } else {
+            self.compressor_2d.compress_to_buffer(
+                &self.device,
+                command_encoder,
+                &params,
+                &target_buffer,
+            );
+        }
+
+        let mappable_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: buffer_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        command_encoder.copy_buffer_to_buffer(&target_buffer, 0, &mappable_buffer, 0, buffer_size);
+
+        mappable_buffer
+    }
+}
+
+/// Compressed mip levels that are still being processed by the device.
+pub struct Submission {
+    buffers: Vec<wgpu::Buffer>,
+}
@@ -0,0 +1,251 @@
+use crate::Result;
+use clap::ArgEnum;
+use std::path::Path;
+use wgpu_bc6h_compression::{decode::f16_to_f32, openexr, pfm, radiance, HdrImage};
+
+pub struct Input {
+    /// Cubemaps are stored as images with a depth of 6, one slice per face.
+    pub image: HdrImage,
+    pub is_cubemap: bool,
+}
+
+/// How the faces of a cubemap are arranged within a single 2D image.
+#[derive(Clone, Copy, Debug, ArgEnum)]
+pub enum CubemapLayout {
+    /// A 4x3 cross, with the -X, +Z, +X and -Z faces in the middle row.
+    HorizontalCross,
+    /// A 3x4 cross, with the -X, +Z and +X faces in the second row and the -Z face upside-down
+    /// at the bottom.
+    VerticalCross,
+    /// The +X, -X, +Y, -Y, +Z and -Z faces from left to right.
+    HorizontalStrip,
+    /// The +X, -X, +Y, -Y, +Z and -Z faces from top to bottom.
+    VerticalStrip,
+}
+
+/// The file extensions of the supported input formats.
+pub const EXTENSIONS: &[&str] = &["hdr", "pfm", "exr", "dds", "ktx2"];
+
+pub fn read(path: &Path, layer: Option<&str>, cubemap: Option<CubemapLayout>) -> Result<Input> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    let context = |error: Box<dyn std::error::Error>| -> Box<dyn std::error::Error> {
+        format!("failed to read '{}': {}", path.display(), error).into()
+    };
+
+    if layer.is_some() && extension.as_deref() != Some("exr") {
+        return Err("--layer can only be used with .exr inputs".into());
+    }
+
+    let input = match extension.as_deref() {
+        Some("hdr") => {
+            read_2d(radiance::read_hdr(open(path)?).map_err(|error| context(error.into()))?)
+        }
+        Some("pfm") => read_2d(pfm::read_pfm(open(path)?).map_err(|error| context(error.into()))?),
+        Some("exr") => {
+            read_2d(openexr::read_exr(path, layer).map_err(|error| context(error.into()))?)
+        }
+        Some("dds") => read_dds(path).map_err(context)?,
+        Some("ktx2") => read_ktx2(path).map_err(context)?,
+        _ => {
+            return Err(format!(
+                "unsupported input '{}': expected a .hdr, .exr, .pfm, .dds or .ktx2 file",
+                path.display()
+            )
+            .into())
+        }
+    };
+
+    if input.image.pixels.is_empty() {
+        return Err(format!("'{}' has no texels", path.display()).into());
+    }
+
+    match cubemap {
+        None => Ok(input),
+        Some(_) if input.is_cubemap || input.image.is_3d() => {
+            Err("--cubemap can only be used with 2D inputs".into())
+        }
+        Some(layout) => Ok(Input {
+            image: extract_faces(&input.image, layout)?,
+            is_cubemap: true,
+        }),
+    }
+}
+
+fn open(path: &Path) -> Result<std::io::BufReader<std::fs::File>> {
+    let file = std::fs::File::open(path)
+        .map_err(|error| format!("failed to open '{}': {}", path.display(), error))?;
+    Ok(std::io::BufReader::new(file))
+}
+
+fn read_2d(image: HdrImage) -> Input {
+    Input {
+        image,
+        is_cubemap: false,
+    }
+}
+
+/// Only RGBA float and half-float formats can be read, and only the first mip level is used.
+fn read_dds(path: &Path) -> Result<Input> {
+    let dds = ddsfile::Dds::read(open(path)?)?;
+
+    let half = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+        (Some(ddsfile::DxgiFormat::R32G32B32A32_Float), _)
+        | (_, Some(ddsfile::D3DFormat::A32B32G32R32F)) => false,
+        (Some(ddsfile::DxgiFormat::R16G16B16A16_Float), _)
+        | (_, Some(ddsfile::D3DFormat::A16B16G16R16F)) => true,
+        (dxgi, d3d) => {
+            return Err(format!(
+                "unsupported format {:?}, expected R32G32B32A32_Float or R16G16B16A16_Float",
+                dxgi.map(|format| format!("{:?}", format))
+                    .or_else(|| d3d.map(|format| format!("{:?}", format)))
+            )
+            .into())
+        }
+    };
+
+    let is_cubemap = dds.header.caps2.contains(ddsfile::Caps2::CUBEMAP)
+        || dds
+            .header10
+            .as_ref()
+            .is_some_and(|header10| header10.misc_flag.contains(ddsfile::MiscFlag::TEXTURECUBE));
+
+    let width = dds.get_width();
+    let height = dds.get_height();
+    let depth = dds.get_depth().max(1);
+    let bytes_per_pixel = if half { 8 } else { 16 };
+    let level_size = width as usize * height as usize * depth as usize * bytes_per_pixel;
+
+    let (faces, stride) = if is_cubemap {
+        (6, dds.get_array_stride()? as usize)
+    } else {
+        (1, 0)
+    };
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * depth as usize * faces);
+
+    for face in 0..faces {
+        let bytes = dds
+            .data
+            .get(face * stride..face * stride + level_size)
+            .ok_or("the file is truncated")?;
+        pixels.extend(read_pixels(bytes, half));
+    }
+
+    Ok(Input {
+        image: HdrImage::new(width, height, depth * faces as u32, pixels),
+        is_cubemap,
+    })
+}
+
+/// Only RGBA float and half-float formats can be read, and only the first mip level is used.
+fn read_ktx2(path: &Path) -> Result<Input> {
+    let bytes = std::fs::read(path)?;
+    let reader = ktx2::Reader::new(&bytes).map_err(|error| format!("{:?}", error))?;
+    let header = reader.header();
+
+    let half = match header.format {
+        Some(ktx2::Format::R32G32B32A32_SFLOAT) => false,
+        Some(ktx2::Format::R16G16B16A16_SFLOAT) => true,
+        format => {
+            return Err(format!(
+                "unsupported format {:?}, expected R32G32B32A32_SFLOAT or R16G16B16A16_SFLOAT",
+                format
+            )
+            .into())
+        }
+    };
+
+    if header.layer_count > 1 {
+        return Err("array textures are not supported".into());
+    }
+
+    let level = reader.levels().next().ok_or("the file has no mip levels")?;
+
+    let data = match header.supercompression_scheme {
+        None => level.data.to_vec(),
+        Some(ktx2::SupercompressionScheme::Zstandard) => {
+            zstd::bulk::decompress(level.data, level.uncompressed_byte_length as usize)?
+        }
+        Some(scheme) => return Err(format!("unsupported supercompression {:?}", scheme).into()),
+    };
+
+    let is_cubemap = header.face_count == 6;
+    let depth = header.pixel_depth.max(1) * header.face_count;
+    let level_size = header.pixel_width as usize
+        * header.pixel_height.max(1) as usize
+        * depth as usize
+        * if half { 8 } else { 16 };
+
+    let bytes = data.get(..level_size).ok_or("the file is truncated")?;
+
+    Ok(Input {
+        image: HdrImage::new(
+            header.pixel_width,
+            header.pixel_height.max(1),
+            depth,
+            read_pixels(bytes, half).collect(),
+        ),
+        is_cubemap,
+    })
+}
+
+fn read_pixels(bytes: &[u8], half: bool) -> impl Iterator<Item = [f32; 4]> + '_ {
+    let texel_size = if half { 8 } else { 16 };
+
+    bytes.chunks_exact(texel_size).map(move |texel| {
+        let mut pixel = [0.0; 4];
+        for (i, value) in pixel.iter_mut().enumerate() {
+            *value = if half {
+                f16_to_f32(u16::from_le_bytes([texel[i * 2], texel[i * 2 + 1]]))
+            } else {
+                f32::from_le_bytes(texel[i * 4..i * 4 + 4].try_into().unwrap())
+            };
+        }
+        pixel
+    })
+}
+
+fn extract_faces(image: &HdrImage, layout: CubemapLayout) -> Result<HdrImage> {
+    // Face positions in units of the face size, in the order +X, -X, +Y, -Y, +Z, -Z.
+    let (columns, rows, positions) = match layout {
+        CubemapLayout::HorizontalCross => (4, 3, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)]),
+        CubemapLayout::VerticalCross => (3, 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)]),
+        CubemapLayout::HorizontalStrip => (6, 1, [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)]),
+        CubemapLayout::VerticalStrip => (1, 6, [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)]),
+    };
+
+    let size = image.width / columns;
+
+    if size == 0 || image.width != size * columns || image.height != size * rows {
+        return Err(format!(
+            "a {}x{} image can't be split into square cubemap faces arranged {}x{}",
+            image.width, image.height, columns, rows
+        )
+        .into());
+    }
+
+    let mut pixels = Vec::with_capacity(size as usize * size as usize * 6);
+
+    for (face, (column, row)) in positions.iter().enumerate() {
+        let upside_down = matches!(layout, CubemapLayout::VerticalCross) && face == 5;
+
+        for y in 0..size {
+            for x in 0..size {
+                let (x, y) = if upside_down {
+                    (size - 1 - x, size - 1 - y)
+                } else {
+                    (x, y)
+                };
+                let source_x = column * size + x;
+                let source_y = row * size + y;
+                pixels.push(image.pixels[(source_y * image.width + source_x) as usize]);
+            }
+        }
+    }
+
+    Ok(HdrImage::new(size, size, 6, pixels))
+}
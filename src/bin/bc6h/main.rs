@@ -0,0 +1,209 @@
+//! A command-line BC6H compressor, built with the `cli` feature.
+
+mod batch;
+mod gpu;
+mod input;
+mod output;
+
+use clap::{ArgEnum, Parser};
+use std::path::{Path, PathBuf};
+use wgpu_bc6h_compression::{decode, HdrImage, Quality};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Compress HDR images into BC6H DDS or KTX2 textures.
+#[derive(Parser)]
+#[clap(version)]
+struct Args {
+    /// The input image, in `.hdr`, `.exr`, `.pfm`, `.dds` or `.ktx2` format, or a directory of
+    /// images to compress in batch mode.
+    input: PathBuf,
+    /// The output texture, in `.dds` or `.ktx2` format, or the directory to write to in batch
+    /// mode.
+    output: PathBuf,
+    /// The format of the textures written in batch mode.
+    #[clap(long, arg_enum, default_value = "ktx2")]
+    format: output::Format,
+    /// The quality preset to compress with.
+    #[clap(long, arg_enum, default_value = "normal")]
+    quality: QualityPreset,
+    /// The number of two-region partitions to fully encode with `--quality refined`, from 1 to
+    /// 32.
+    #[clap(long, default_value = "4")]
+    partitions: u32,
+    /// Fit the endpoints of the two-region partitions by least squares with `--quality refined`,
+    /// as `--quality high` always does.
+    #[clap(long)]
+    optimize_endpoints: bool,
+    /// How many times to refit the endpoints to the indices with the lowest error with
+    /// `--quality refined`, from 0 to 7. `--quality high` refines twice.
+    #[clap(long, default_value = "0")]
+    refinement_passes: u32,
+    /// Generate a full mip chain.
+    #[clap(long)]
+    mips: bool,
+    /// Treat the input as a cubemap with its faces arranged in this layout. Cubemap `.dds` and
+    /// `.ktx2` inputs are detected automatically.
+    #[clap(long, arg_enum)]
+    cubemap: Option<input::CubemapLayout>,
+    /// Write signed (`BC6H_SF16`) instead of unsigned (`BC6H_UF16`) blocks. The compressors only
+    /// encode non-negative values, so negative texels are still clamped to zero, and the blocks
+    /// are re-packed with a bit less endpoint precision.
+    #[clap(long)]
+    signed: bool,
+    /// The supercompression to apply to `.ktx2` outputs.
+    #[clap(long, arg_enum, default_value = "none")]
+    supercompression: Supercompression,
+    /// The Zstandard compression level, from 1 to 22.
+    #[clap(long, default_value = "3")]
+    zstd_level: i32,
+    /// The layer of an `.exr` input to read, such as `diffuse` for the `diffuse.R`, `diffuse.G`
+    /// and `diffuse.B` channels.
+    #[clap(long)]
+    layer: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, ArgEnum)]
+enum QualityPreset {
+    Fast,
+    Normal,
+    Refined,
+    High,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ArgEnum)]
+enum Supercompression {
+    None,
+    Zstd,
+}
+
+/// A compressed texture, with the blocks of each mip level laid out face by face (or slice by
+/// slice).
+pub struct Compressed {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub is_cubemap: bool,
+    pub is_signed: bool,
+    pub levels: Vec<Vec<u8>>,
+}
+
+fn main() {
+    if let Err(error) = run(Args::parse()) {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    if args.input.is_dir() {
+        return batch::run(&args);
+    }
+
+    let output_format = output::Format::from_path(&args.output)?;
+    check_supercompression(&args, output_format)?;
+
+    let gpu = gpu::Gpu::new()?;
+
+    let (compressed, levels) = load(&args, &args.input)?;
+    let mut levels = gpu.read(gpu.submit(&levels, args.quality()))?;
+    if args.signed {
+        to_signed(&mut levels)?;
+    }
+
+    output::write(
+        &args.output,
+        output_format,
+        &Compressed {
+            levels,
+            ..compressed
+        },
+        args.zstd_level(),
+    )
+}
+
+impl Args {
+    fn quality(&self) -> Quality {
+        match self.quality {
+            QualityPreset::Fast => Quality::Fast,
+            QualityPreset::Normal => Quality::Normal,
+            QualityPreset::Refined => Quality::Refined {
+                partitions: self.partitions,
+                optimize_endpoints: self.optimize_endpoints,
+                refinement_passes: self.refinement_passes,
+            },
+            QualityPreset::High => Quality::High,
+        }
+    }
+
+    fn zstd_level(&self) -> Option<i32> {
+        match self.supercompression {
+            Supercompression::None => None,
+            Supercompression::Zstd => Some(self.zstd_level),
+        }
+    }
+}
+
+fn check_supercompression(args: &Args, output_format: output::Format) -> Result<()> {
+    if args.supercompression == Supercompression::Zstd && output_format != output::Format::Ktx2 {
+        return Err("supercompression is only supported for .ktx2 outputs".into());
+    }
+
+    Ok(())
+}
+
+/// Read an input and generate its mip chain, returning the levels to compress alongside a
+/// description of the texture with its levels still empty.
+fn load(args: &Args, path: &Path) -> Result<(Compressed, Vec<HdrImage>)> {
+    let input = input::read(path, args.layer.as_deref(), args.cubemap)?;
+
+    if args.mips && input.image.is_3d() && !input.is_cubemap {
+        return Err("mip generation is not supported for 3D textures".into());
+    }
+
+    let compressed = Compressed {
+        width: input.image.width,
+        height: input.image.height,
+        depth: input.image.depth,
+        is_cubemap: input.is_cubemap,
+        is_signed: args.signed,
+        levels: Vec::new(),
+    };
+
+    Ok((compressed, mip_chain(input.image, args.mips)))
+}
+
+/// Re-pack the unsigned blocks the compressors write as signed ones, for `--signed`.
+fn to_signed(levels: &mut [Vec<u8>]) -> Result<()> {
+    for block in levels
+        .iter_mut()
+        .flat_map(|level| level.chunks_exact_mut(16))
+    {
+        let block: &mut [u8; 16] = block.try_into().unwrap();
+        *block = decode::to_signed_block(block)
+            .ok_or("a compressed block couldn't be re-packed as a signed one")?;
+    }
+
+    Ok(())
+}
+
+fn mip_chain(image: HdrImage, mips: bool) -> Vec<HdrImage> {
+    let mut levels = vec![image];
+
+    if !mips {
+        return levels;
+    }
+
+    loop {
+        let last = levels.last().unwrap();
+
+        if last.width <= 1 && last.height <= 1 {
+            break;
+        }
+
+        let next = last.downsampled();
+        levels.push(next);
+    }
+
+    levels
+}
@@ -0,0 +1,222 @@
+use crate::{Compressed, Result};
+use clap::ArgEnum;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, ArgEnum)]
+pub enum Format {
+    Dds,
+    Ktx2,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("dds") => Ok(Self::Dds),
+            Some("ktx2") => Ok(Self::Ktx2),
+            _ => Err(format!(
+                "unsupported output '{}': expected a .dds or .ktx2 file",
+                path.display()
+            )
+            .into()),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Dds => "dds",
+            Self::Ktx2 => "ktx2",
+        }
+    }
+}
+
+pub fn write(
+    path: &Path,
+    format: Format,
+    compressed: &Compressed,
+    zstd_level: Option<i32>,
+) -> Result<()> {
+    let bytes = match format {
+        Format::Dds => write_dds(compressed)?,
+        Format::Ktx2 => write_ktx2(compressed, zstd_level)?,
+    };
+
+    std::fs::write(path, bytes)
+        .map_err(|error| format!("failed to write '{}': {}", path.display(), error).into())
+}
+
+fn write_dds(compressed: &Compressed) -> Result<Vec<u8>> {
+    let is_3d = compressed.depth > 1 && !compressed.is_cubemap;
+
+    let mut dds = ddsfile::Dds::new_dxgi(ddsfile::NewDxgiParams {
+        width: compressed.width,
+        height: compressed.height,
+        depth: if is_3d { Some(compressed.depth) } else { None },
+        format: if compressed.is_signed {
+            ddsfile::DxgiFormat::BC6H_SF16
+        } else {
+            ddsfile::DxgiFormat::BC6H_UF16
+        },
+        mipmap_levels: if compressed.levels.len() > 1 {
+            Some(compressed.levels.len() as u32)
+        } else {
+            None
+        },
+        array_layers: if compressed.is_cubemap { Some(6) } else { None },
+        caps2: if compressed.is_cubemap {
+            Some(ddsfile::Caps2::CUBEMAP | ddsfile::Caps2::CUBEMAP_ALLFACES)
+        } else {
+            None
+        },
+        is_cubemap: compressed.is_cubemap,
+        resource_dimension: if is_3d {
+            ddsfile::D3D10ResourceDimension::Texture3D
+        } else {
+            ddsfile::D3D10ResourceDimension::Texture2D
+        },
+        alpha_mode: ddsfile::AlphaMode::Unknown,
+    })?;
+
+    // DDS stores the full mip chain of each face before moving onto the next, while the levels
+    // are stored with all of their faces together.
+    let faces = if compressed.is_cubemap { 6 } else { 1 };
+
+    dds.data = (0..faces)
+        .flat_map(|face| {
+            compressed.levels.iter().flat_map(move |level| {
+                let face_size = level.len() / faces;
+                &level[face * face_size..(face + 1) * face_size]
+            })
+        })
+        .copied()
+        .collect();
+
+    let mut bytes = Vec::new();
+    dds.write(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_ktx2(compressed: &Compressed, zstd_level: Option<i32>) -> Result<Vec<u8>> {
+    let levels = compressed
+        .levels
+        .iter()
+        .map(|level| match zstd_level {
+            Some(zstd_level) => Ok(zstd::bulk::compress(level, zstd_level)?),
+            None => Ok(level.clone()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let dfd = basic_data_format_descriptor(compressed.is_signed);
+
+    let level_index_offset = ktx2::Header::LENGTH;
+    let dfd_offset = level_index_offset + levels.len() * ktx2::LevelIndex::LENGTH;
+    let data_offset = dfd_offset + dfd.len();
+
+    // Without supercompression, levels need to be aligned to the 16-byte block size.
+    let alignment = if zstd_level.is_some() { 1 } else { 16 };
+
+    // Levels are stored from the smallest to the largest.
+    let mut data = Vec::new();
+    let mut level_indices = vec![None; levels.len()];
+
+    for (i, level) in levels.iter().enumerate().rev() {
+        while !(data_offset + data.len()).is_multiple_of(alignment) {
+            data.push(0);
+        }
+
+        level_indices[i] = Some(ktx2::LevelIndex {
+            byte_offset: (data_offset + data.len()) as u64,
+            byte_length: level.len() as u64,
+            uncompressed_byte_length: compressed.levels[i].len() as u64,
+        });
+
+        data.extend_from_slice(level);
+    }
+
+    let header = ktx2::Header {
+        format: Some(if compressed.is_signed {
+            ktx2::Format::BC6H_SFLOAT_BLOCK
+        } else {
+            ktx2::Format::BC6H_UFLOAT_BLOCK
+        }),
+        type_size: 1,
+        pixel_width: compressed.width,
+        pixel_height: compressed.height,
+        pixel_depth: if compressed.depth > 1 && !compressed.is_cubemap {
+            compressed.depth
+        } else {
+            0
+        },
+        layer_count: 0,
+        face_count: if compressed.is_cubemap { 6 } else { 1 },
+        level_count: levels.len() as u32,
+        supercompression_scheme: zstd_level.map(|_| ktx2::SupercompressionScheme::Zstandard),
+        index: ktx2::Index {
+            dfd_byte_offset: dfd_offset as u32,
+            dfd_byte_length: dfd.len() as u32,
+            kvd_byte_offset: 0,
+            kvd_byte_length: 0,
+            sgd_byte_offset: 0,
+            sgd_byte_length: 0,
+        },
+    };
+
+    let mut bytes = Vec::with_capacity(data_offset + data.len());
+    bytes.extend_from_slice(&header.as_bytes());
+    for level_index in level_indices.into_iter().flatten() {
+        bytes.extend_from_slice(&level_index.as_bytes());
+    }
+    bytes.extend_from_slice(&dfd);
+    bytes.extend_from_slice(&data);
+    Ok(bytes)
+}
+
+/// The data format descriptor for `VK_FORMAT_BC6H_UFLOAT_BLOCK`, or `VK_FORMAT_BC6H_SFLOAT_BLOCK`
+/// if `signed`: a basic descriptor block with a single 128-bit floating-point sample.
+fn basic_data_format_descriptor(signed: bool) -> Vec<u8> {
+    const DESCRIPTOR_BLOCK_SIZE: u16 = 24 + 16;
+    const VERSION: u16 = 2;
+    const COLOR_MODEL_BC6H: u8 = 133;
+    const COLOR_PRIMARIES_BT709: u8 = 1;
+    const TRANSFER_FUNCTION_LINEAR: u8 = 1;
+    const QUALIFIER_FLOAT: u8 = 0x80;
+    const QUALIFIER_SIGNED: u8 = 0x40;
+
+    let mut dfd = Vec::with_capacity(4 + DESCRIPTOR_BLOCK_SIZE as usize);
+
+    dfd.extend_from_slice(&(4 + DESCRIPTOR_BLOCK_SIZE as u32).to_le_bytes());
+
+    // Vendor and descriptor type, both zero for a Khronos basic descriptor block.
+    dfd.extend_from_slice(&0u32.to_le_bytes());
+    dfd.extend_from_slice(&VERSION.to_le_bytes());
+    dfd.extend_from_slice(&DESCRIPTOR_BLOCK_SIZE.to_le_bytes());
+    dfd.extend_from_slice(&[
+        COLOR_MODEL_BC6H,
+        COLOR_PRIMARIES_BT709,
+        TRANSFER_FUNCTION_LINEAR,
+        0,
+    ]);
+    // Texel block dimensions, minus one.
+    dfd.extend_from_slice(&[3, 3, 0, 0]);
+    // Bytes per plane.
+    dfd.extend_from_slice(&[16, 0, 0, 0, 0, 0, 0, 0]);
+
+    // The sample: bit offset, bit length minus one, channel and qualifiers, position, lower and
+    // upper values.
+    dfd.extend_from_slice(&0u16.to_le_bytes());
+    if signed {
+        dfd.extend_from_slice(&[127, QUALIFIER_FLOAT | QUALIFIER_SIGNED]);
+    } else {
+        dfd.extend_from_slice(&[127, QUALIFIER_FLOAT]);
+    }
+    dfd.extend_from_slice(&[0, 0, 0, 0]);
+    let lower = if signed { -1f32 } else { 0f32 };
+    dfd.extend_from_slice(&lower.to_bits().to_le_bytes());
+    dfd.extend_from_slice(&1f32.to_bits().to_le_bytes());
+
+    dfd
+}
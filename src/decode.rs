@@ -0,0 +1,769 @@
+//! A CPU decoder for BC6H blocks, for inspecting and diffing the output of the compressors.
+//!
+//! All 14 modes of both [`wgpu::TextureFormat::Bc6hRgbUfloat`] and
+//! [`wgpu::TextureFormat::Bc6hRgbSfloat`] are supported, not just the ones the shaders emit.
+//! Unsigned blocks can also be re-packed as signed ones with [`to_signed_block`].
+
+use crate::HdrImage;
+
+/// Decode a single 16-byte block into its 4x4 texels, in row-major order.
+pub fn decode_block(block: &[u8; 16], signed: bool) -> [[f32; 3]; 16] {
+    let (mode, mut endpoints, partition, index_offset) = match read_header(block) {
+        Some(header) => header,
+        // Reserved modes decode to black.
+        None => return [[0.0; 3]; 16],
+    };
+
+    for channel in 0..3 {
+        let endpoint_bits = mode.endpoint_bits;
+        let delta_bits = mode.delta_bits[channel];
+
+        if signed {
+            endpoints[0][channel] = sign_extend(endpoints[0][channel], endpoint_bits);
+        }
+
+        let base = endpoints[0][channel];
+
+        for endpoint in endpoints.iter_mut().skip(1) {
+            if mode.transformed {
+                let delta = sign_extend(endpoint[channel], delta_bits);
+                endpoint[channel] = (base + delta) & ((1 << endpoint_bits) - 1);
+                if signed {
+                    endpoint[channel] = sign_extend(endpoint[channel], endpoint_bits);
+                }
+            } else if signed {
+                endpoint[channel] = sign_extend(endpoint[channel], endpoint_bits);
+            }
+        }
+
+        for endpoint in &mut endpoints {
+            endpoint[channel] = unquantize(endpoint[channel], endpoint_bits, signed);
+        }
+    }
+
+    let mut bits = BitReader::new(block);
+    bits.skip(index_offset);
+
+    let mut texels = [[0.0; 3]; 16];
+
+    for (i, texel) in texels.iter_mut().enumerate() {
+        let (region, index_bits, weights) = if mode.two_regions {
+            let region = pattern(partition, i as u32);
+            let anchor = i == 0 || i as u32 == pattern_fixup_id(partition);
+            (region as usize, 3 - anchor as u32, &WEIGHTS_3[..])
+        } else {
+            (0, 4 - (i == 0) as u32, &WEIGHTS_4[..])
+        };
+
+        let weight = weights[bits.read(index_bits) as usize];
+        let endpoint_0 = endpoints[region * 2];
+        let endpoint_1 = endpoints[region * 2 + 1];
+
+        for channel in 0..3 {
+            let value =
+                (endpoint_0[channel] * (64 - weight) + endpoint_1[channel] * weight + 32) >> 6;
+            texel[channel] = f16_to_f32(finish_unquantize(value, signed));
+        }
+    }
+
+    texels
+}
+
+/// Re-pack a [`wgpu::TextureFormat::Bc6hRgbUfloat`] block, such as the compressors write, as a
+/// [`wgpu::TextureFormat::Bc6hRgbSfloat`] block with the same mode, partition and indices.
+///
+/// A signed endpoint spends one of its bits on the sign, so each endpoint is rounded to the
+/// closest value with a bit less precision, and the texels can move by about that much.
+///
+/// `None` for blocks in the reserved modes, and for ones that rely on an endpoint wrapping around
+/// when its delta is added, which the compressors never write.
+pub fn to_signed_block(block: &[u8; 16]) -> Option<[u8; 16]> {
+    let (mode, mut endpoints, partition, index_offset) = read_header(block)?;
+
+    let endpoint_mask = (1 << mode.endpoint_bits) - 1;
+
+    for channel in 0..3 {
+        let delta_bits = mode.delta_bits[channel];
+        let base = endpoints[0][channel];
+
+        let mut values = [0; 4];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = if mode.transformed && i > 0 {
+                (base + sign_extend(endpoints[i][channel], delta_bits)) & endpoint_mask
+            } else {
+                endpoints[i][channel]
+            };
+        }
+
+        let values = values.map(|value| signed_endpoint(value, mode.endpoint_bits));
+
+        for (i, endpoint) in endpoints.iter_mut().enumerate() {
+            endpoint[channel] = if mode.transformed && i > 0 {
+                // Rounding both endpoints to half their precision about halves the delta
+                // between them, so it only stops fitting if it wrapped around before.
+                let delta = values[i] - values[0];
+                let limit = 1 << (delta_bits - 1);
+                if delta < -limit || delta >= limit {
+                    return None;
+                }
+                delta & ((1 << delta_bits) - 1)
+            } else {
+                values[i] & endpoint_mask
+            };
+        }
+    }
+
+    // The header is the same length in both formats, so the indices stay where they are.
+    let mut header = 0_u128;
+    let mut position = 0;
+    let mut write = |value: u32, count: u32| {
+        header |= (value as u128 & ((1 << count) - 1)) << position;
+        position += count;
+    };
+
+    write(mode.bits, if mode.bits < 2 { 2 } else { 5 });
+    for &(endpoint, channel, bit) in mode.layout {
+        write(
+            (endpoints[endpoint as usize][channel as usize] >> bit) as u32,
+            1,
+        );
+    }
+    if mode.two_regions {
+        write(partition, 5);
+    }
+
+    let indices = u128::from_le_bytes(*block) & (u128::MAX << index_offset);
+    Some((header | indices).to_le_bytes())
+}
+
+/// Decode the output of a compressor (as written by `compress_to_buffer`) into an image.
+pub fn decode_blocks(blocks: &[u8], extent: wgpu::Extent3d, signed: bool) -> HdrImage {
+    let width_in_blocks = extent.width as usize / 4;
+    let height_in_blocks = extent.height as usize / 4;
+    let depth = extent.depth_or_array_layers as usize;
+
+    assert!(blocks.len() >= width_in_blocks * height_in_blocks * depth * 16);
+
+    let mut pixels = vec![[0.0; 4]; extent.width as usize * extent.height as usize * depth];
+
+    for (i, block) in blocks
+        .chunks_exact(16)
+        .take(width_in_blocks * height_in_blocks * depth)
+        .enumerate()
+    {
+        let block_x = i % width_in_blocks;
+        let block_y = (i / width_in_blocks) % height_in_blocks;
+        let z = i / (width_in_blocks * height_in_blocks);
+
+        let texels = decode_block(block.try_into().unwrap(), signed);
+
+        for (j, [r, g, b]) in texels.iter().copied().enumerate() {
+            let x = block_x * 4 + j % 4;
+            let y = block_y * 4 + j / 4;
+            pixels[(z * extent.height as usize + y) * extent.width as usize + x] = [r, g, b, 1.0];
+        }
+    }
+
+    HdrImage::new(
+        extent.width,
+        extent.height,
+        extent.depth_or_array_layers,
+        pixels,
+    )
+}
+
+/// Read the mode of a block, its endpoints w, x, y and z by red, green and blue as they are
+/// stored, its partition and the bit its indices start at. `None` for the reserved modes.
+fn read_header(block: &[u8; 16]) -> Option<(&'static Mode, [[i32; 3]; 4], u32, u32)> {
+    let mut bits = BitReader::new(block);
+
+    let mode_bits = match bits.read(2) {
+        mode @ (0 | 1) => mode,
+        low => low | (bits.read(3) << 2),
+    };
+
+    let mode = MODES.iter().find(|mode| mode.bits == mode_bits)?;
+
+    let mut endpoints = [[0_i32; 3]; 4];
+
+    for &(endpoint, channel, bit) in mode.layout {
+        endpoints[endpoint as usize][channel as usize] |= (bits.read(1) as i32) << bit;
+    }
+
+    let partition = if mode.two_regions { bits.read(5) } else { 0 };
+
+    Some((mode, endpoints, partition, bits.position()))
+}
+
+/// The signed endpoint of `bits` bits closest to an unsigned one, compared by the half floats they
+/// finish unquantizing to.
+fn signed_endpoint(value: i32, bits: u32) -> i32 {
+    let unsigned = finish_unquantize(unquantize(value, bits, false), false) as i32;
+    let max = (1 << (bits - 1)) - 1;
+
+    (value / 2 - 1..=value / 2 + 1)
+        .map(|candidate| candidate.clamp(0, max))
+        .min_by_key(|&candidate| {
+            (finish_unquantize(unquantize(candidate, bits, true), true) as i32 - unsigned).abs()
+        })
+        .unwrap()
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8; 16],
+    position: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8; 16]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.bytes[(self.position / 8) as usize];
+            value |= (((byte >> (self.position % 8)) & 1) as u32) << i;
+            self.position += 1;
+        }
+        value
+    }
+
+    fn skip(&mut self, count: u32) {
+        self.position += count;
+    }
+
+    fn position(&self) -> u32 {
+        self.position
+    }
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+fn unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if !signed {
+        if bits >= 15 {
+            value
+        } else if value == 0 {
+            0
+        } else if value == (1 << bits) - 1 {
+            0xFFFF
+        } else {
+            ((value << 16) + 0x8000) >> bits
+        }
+    } else if bits >= 16 {
+        value
+    } else {
+        let magnitude = value.abs();
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if value < 0 {
+            -unquantized
+        } else {
+            unquantized
+        }
+    }
+}
+
+fn finish_unquantize(value: i32, signed: bool) -> u16 {
+    if !signed {
+        ((value * 31) >> 6) as u16
+    } else if value < 0 {
+        0x8000 | (((-value) * 31) >> 5) as u16
+    } else {
+        ((value * 31) >> 5) as u16
+    }
+}
+
+/// Convert the bits of a half-precision float to a `f32`.
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // Subnormal: renormalize the mantissa.
+            let shift = mantissa.leading_zeros() - 21;
+            let mantissa = (mantissa << shift) & 0x3FF;
+            sign | ((113 - shift) << 23) | (mantissa << 13)
+        }
+        0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(bits)
+}
+
+// The same partition tables as in the shader.
+fn pattern_fixup_id(partition: u32) -> u32 {
+    if (845414400 >> partition) & 0x1 != 0 {
+        8
+    } else if (3441033216_u32 >> partition) & 0x1 != 0 {
+        2
+    } else {
+        15
+    }
+}
+
+fn pattern(partition: u32, i: u32) -> u32 {
+    const PATTERNS: [u32; 16] = [
+        2290666700, 3972591342, 4276930688, 3967876808, 4293707776, 3892379264, 4278255592,
+        4026597360, 9369360, 147747072, 1930428556, 2362323200, 823134348, 913073766, 267393000,
+        966553998,
+    ];
+
+    let enc = PATTERNS[partition as usize / 2] >> ((partition % 2) * 16);
+    (enc >> i) & 0x1
+}
+
+const WEIGHTS_3: [i32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [i32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+struct Mode {
+    bits: u32,
+    two_regions: bool,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    layout: &'static [(u8, u8, u8)],
+}
+
+// Endpoints.
+const W: u8 = 0;
+const X: u8 = 1;
+const Y: u8 = 2;
+const Z: u8 = 3;
+// Channels.
+const R: u8 = 0;
+const G: u8 = 1;
+const B: u8 = 2;
+
+/// Expands `field[hi:lo]` into individual bits, least significant first.
+macro_rules! bits {
+    ($endpoint:expr, $channel:expr, $hi:expr, $lo:expr) => {{
+        let mut bits = [($endpoint, $channel, 0_u8); $hi - $lo + 1];
+        let mut i = 0;
+        while i < bits.len() {
+            bits[i].2 = ($lo + i) as u8;
+            i += 1;
+        }
+        bits
+    }};
+}
+
+macro_rules! layout {
+    ($($field:expr),* $(,)?) => {{
+        const LEN: usize = 0 $(+ $field.len())*;
+        const LAYOUT: [(u8, u8, u8); LEN] = {
+            let mut layout = [(0, 0, 0); LEN];
+            let mut offset = 0;
+            $(
+                let field = $field;
+                let mut i = 0;
+                while i < field.len() {
+                    layout[offset] = field[i];
+                    offset += 1;
+                    i += 1;
+                }
+            )*
+            let _ = offset;
+            layout
+        };
+        &LAYOUT
+    }};
+}
+
+// Bit layouts from the BC6H format specification, in the order they are stored.
+const MODES: [Mode; 14] = [
+    Mode {
+        bits: 0b00,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 10,
+        delta_bits: [5, 5, 5],
+        layout: layout!(
+            bits!(Y, G, 4, 4),
+            bits!(Y, B, 4, 4),
+            bits!(Z, B, 4, 4),
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 4, 0),
+            bits!(Z, G, 4, 4),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 4, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 4, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 4, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 4, 0),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b01,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 7,
+        delta_bits: [6, 6, 6],
+        layout: layout!(
+            bits!(Y, G, 5, 5),
+            bits!(Z, G, 4, 4),
+            bits!(Z, G, 5, 5),
+            bits!(W, R, 6, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 4, 4),
+            bits!(W, G, 6, 0),
+            bits!(Y, B, 5, 5),
+            bits!(Z, B, 2, 2),
+            bits!(Y, G, 4, 4),
+            bits!(W, B, 6, 0),
+            bits!(Z, B, 3, 3),
+            bits!(Z, B, 5, 5),
+            bits!(Z, B, 4, 4),
+            bits!(X, R, 5, 0),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 5, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 5, 0),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 5, 0),
+            bits!(Z, R, 5, 0),
+        ),
+    },
+    Mode {
+        bits: 0b00010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [5, 4, 4],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 4, 0),
+            bits!(W, R, 10, 10),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 3, 0),
+            bits!(W, G, 10, 10),
+            bits!(Z, B, 0, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 3, 0),
+            bits!(W, B, 10, 10),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 4, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 4, 0),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b00110,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 5, 4],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 3, 0),
+            bits!(W, R, 10, 10),
+            bits!(Z, G, 4, 4),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 4, 0),
+            bits!(W, G, 10, 10),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 3, 0),
+            bits!(W, B, 10, 10),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 3, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 3, 0),
+            bits!(Y, G, 4, 4),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b01010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [4, 4, 5],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 3, 0),
+            bits!(W, R, 10, 10),
+            bits!(Y, B, 4, 4),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 3, 0),
+            bits!(W, G, 10, 10),
+            bits!(Z, B, 0, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 4, 0),
+            bits!(W, B, 10, 10),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 3, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 3, 0),
+            bits!(Z, B, 4, 4),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b01110,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 9,
+        delta_bits: [5, 5, 5],
+        layout: layout!(
+            bits!(W, R, 8, 0),
+            bits!(Y, B, 4, 4),
+            bits!(W, G, 8, 0),
+            bits!(Y, G, 4, 4),
+            bits!(W, B, 8, 0),
+            bits!(Z, B, 4, 4),
+            bits!(X, R, 4, 0),
+            bits!(Z, G, 4, 4),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 4, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 4, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 4, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 4, 0),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b10010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [6, 5, 5],
+        layout: layout!(
+            bits!(W, R, 7, 0),
+            bits!(Z, G, 4, 4),
+            bits!(Y, B, 4, 4),
+            bits!(W, G, 7, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Y, G, 4, 4),
+            bits!(W, B, 7, 0),
+            bits!(Z, B, 3, 3),
+            bits!(Z, B, 4, 4),
+            bits!(X, R, 5, 0),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 4, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 4, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 5, 0),
+            bits!(Z, R, 5, 0),
+        ),
+    },
+    Mode {
+        bits: 0b10110,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 6, 5],
+        layout: layout!(
+            bits!(W, R, 7, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Y, B, 4, 4),
+            bits!(W, G, 7, 0),
+            bits!(Y, G, 5, 5),
+            bits!(Y, G, 4, 4),
+            bits!(W, B, 7, 0),
+            bits!(Z, G, 5, 5),
+            bits!(Z, B, 4, 4),
+            bits!(X, R, 4, 0),
+            bits!(Z, G, 4, 4),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 5, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 4, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 4, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 4, 0),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b11010,
+        two_regions: true,
+        transformed: true,
+        endpoint_bits: 8,
+        delta_bits: [5, 5, 6],
+        layout: layout!(
+            bits!(W, R, 7, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 4, 4),
+            bits!(W, G, 7, 0),
+            bits!(Y, B, 5, 5),
+            bits!(Y, G, 4, 4),
+            bits!(W, B, 7, 0),
+            bits!(Z, B, 5, 5),
+            bits!(Z, B, 4, 4),
+            bits!(X, R, 4, 0),
+            bits!(Z, G, 4, 4),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 4, 0),
+            bits!(Z, B, 0, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 5, 0),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 4, 0),
+            bits!(Z, B, 2, 2),
+            bits!(Z, R, 4, 0),
+            bits!(Z, B, 3, 3),
+        ),
+    },
+    Mode {
+        bits: 0b11110,
+        two_regions: true,
+        transformed: false,
+        endpoint_bits: 6,
+        delta_bits: [6, 6, 6],
+        layout: layout!(
+            bits!(W, R, 5, 0),
+            bits!(Z, G, 4, 4),
+            bits!(Z, B, 0, 0),
+            bits!(Z, B, 1, 1),
+            bits!(Y, B, 4, 4),
+            bits!(W, G, 5, 0),
+            bits!(Y, G, 5, 5),
+            bits!(Y, B, 5, 5),
+            bits!(Z, B, 2, 2),
+            bits!(Y, G, 4, 4),
+            bits!(W, B, 5, 0),
+            bits!(Z, G, 5, 5),
+            bits!(Z, B, 3, 3),
+            bits!(Z, B, 5, 5),
+            bits!(Z, B, 4, 4),
+            bits!(X, R, 5, 0),
+            bits!(Y, G, 3, 0),
+            bits!(X, G, 5, 0),
+            bits!(Z, G, 3, 0),
+            bits!(X, B, 5, 0),
+            bits!(Y, B, 3, 0),
+            bits!(Y, R, 5, 0),
+            bits!(Z, R, 5, 0),
+        ),
+    },
+    Mode {
+        bits: 0b00011,
+        two_regions: false,
+        transformed: false,
+        endpoint_bits: 10,
+        delta_bits: [10, 10, 10],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 9, 0),
+            bits!(X, G, 9, 0),
+            bits!(X, B, 9, 0),
+        ),
+    },
+    Mode {
+        bits: 0b00111,
+        two_regions: false,
+        transformed: true,
+        endpoint_bits: 11,
+        delta_bits: [9, 9, 9],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 8, 0),
+            bits!(W, R, 10, 10),
+            bits!(X, G, 8, 0),
+            bits!(W, G, 10, 10),
+            bits!(X, B, 8, 0),
+            bits!(W, B, 10, 10),
+        ),
+    },
+    // The high bits of the first endpoint in the last two modes are stored reversed.
+    Mode {
+        bits: 0b01011,
+        two_regions: false,
+        transformed: true,
+        endpoint_bits: 12,
+        delta_bits: [8, 8, 8],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 7, 0),
+            bits!(W, R, 11, 11),
+            bits!(W, R, 10, 10),
+            bits!(X, G, 7, 0),
+            bits!(W, G, 11, 11),
+            bits!(W, G, 10, 10),
+            bits!(X, B, 7, 0),
+            bits!(W, B, 11, 11),
+            bits!(W, B, 10, 10),
+        ),
+    },
+    Mode {
+        bits: 0b01111,
+        two_regions: false,
+        transformed: true,
+        endpoint_bits: 16,
+        delta_bits: [4, 4, 4],
+        layout: layout!(
+            bits!(W, R, 9, 0),
+            bits!(W, G, 9, 0),
+            bits!(W, B, 9, 0),
+            bits!(X, R, 3, 0),
+            bits!(W, R, 15, 15),
+            bits!(W, R, 14, 14),
+            bits!(W, R, 13, 13),
+            bits!(W, R, 12, 12),
+            bits!(W, R, 11, 11),
+            bits!(W, R, 10, 10),
+            bits!(X, G, 3, 0),
+            bits!(W, G, 15, 15),
+            bits!(W, G, 14, 14),
+            bits!(W, G, 13, 13),
+            bits!(W, G, 12, 12),
+            bits!(W, G, 11, 11),
+            bits!(W, G, 10, 10),
+            bits!(X, B, 3, 0),
+            bits!(W, B, 15, 15),
+            bits!(W, B, 14, 14),
+            bits!(W, B, 13, 13),
+            bits!(W, B, 12, 12),
+            bits!(W, B, 11, 11),
+            bits!(W, B, 10, 10),
+        ),
+    },
+];
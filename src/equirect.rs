@@ -0,0 +1,165 @@
+use crate::{dispatch_count, TextureParams};
+use wgpu::util::DeviceExt;
+
+/// Projects an equirectangular panorama onto a cubemap, so that a skybox can be compressed from
+/// a single HDRI.
+///
+/// The faces are written to the slices of a [`wgpu::TextureFormat::Rgba32Float`] 3D texture in
+/// the +X, -X, +Y, -Y, +Z, -Z order, which can be bound straight to
+/// [`Compressor3D`](crate::Compressor3D). The centre of the panorama faces -Z.
+pub struct EquirectToCubemap {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl EquirectToCubemap {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device
+            .create_shader_module(&wgpu::include_wgsl!("../shaders/equirect_to_cubemap.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression equirect bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression equirect pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("wgpu-bc6h-compression equirect pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    /// Create a cubemap with `face_size` by `face_size` faces and record the projection into it.
+    /// The texture always has the `TEXTURE_BINDING` and `STORAGE_BINDING` usages.
+    pub fn project(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &ProjectionParams,
+        texture_params: &TextureParams,
+    ) -> wgpu::Texture {
+        let extent = wgpu::Extent3d {
+            width: params.face_size,
+            height: params.face_size,
+            depth_or_array_layers: 6,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: texture_params.label,
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: texture_params.usage
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING,
+        });
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let constants = [params.face_size, params.filter as u32];
+
+        let constant_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::bytes_of(&constants),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: params.bind_group_label,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(params.texture),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: constant_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass =
+            command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch(
+            dispatch_count(params.face_size, 8),
+            dispatch_count(params.face_size, 8),
+            6,
+        );
+
+        texture
+    }
+}
+
+pub struct ProjectionParams<'a> {
+    pub bind_group_label: Option<&'a str>,
+    /// An [`wgpu::TextureFormat::Rgba32Float`] panorama with a 2:1 aspect ratio.
+    pub texture: &'a wgpu::TextureView,
+    /// The width and height of each face. This needs to be a multiple of 4 for the cubemap to
+    /// be compressed.
+    pub face_size: u32,
+    pub filter: Filter,
+}
+
+/// How the panorama is sampled. Both filters are done manually in the shader, as
+/// [`wgpu::TextureFormat::Rgba32Float`] isn't filterable without an extra feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// Interpolates between the nearest 2x2 texels.
+    #[default]
+    Bilinear = 0,
+    /// Catmull-Rom interpolation between the nearest 4x4 texels, which stays sharper when
+    /// magnifying the panorama. Overshoots are clamped to zero.
+    Bicubic = 1,
+}
@@ -0,0 +1,156 @@
+use wgpu::util::DeviceExt;
+
+/// An uncompressed RGBA floating-point image, as read from one of the supported input formats.
+///
+/// Pixels are stored row by row, slice by slice, matching the layout of an
+/// [`wgpu::TextureFormat::Rgba32Float`] texture.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl HdrImage {
+    pub fn new(width: u32, height: u32, depth: u32, pixels: Vec<[f32; 4]>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * depth as usize
+        );
+
+        Self {
+            width,
+            height,
+            depth,
+            pixels,
+        }
+    }
+
+    pub fn extent(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: self.depth,
+        }
+    }
+
+    pub fn is_3d(&self) -> bool {
+        self.depth > 1
+    }
+
+    /// Pad the image so that its width and height are multiples of the 4x4 block size, as
+    /// required by the compressors. The edge texels are repeated into the padding.
+    pub fn padded_to_blocks(&self) -> Self {
+        let width = round_up_to_block(self.width);
+        let height = round_up_to_block(self.height);
+
+        if width == self.width && height == self.height {
+            return self.clone();
+        }
+
+        // There are no edge texels to repeat.
+        if self.pixels.is_empty() {
+            return Self::new(width, height, self.depth, Vec::new());
+        }
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * self.depth as usize);
+
+        for z in 0..self.depth {
+            for y in 0..height {
+                let y = y.min(self.height - 1);
+                for x in 0..width {
+                    let x = x.min(self.width - 1);
+                    pixels.push(self.pixels[self.index(x, y, z)]);
+                }
+            }
+        }
+
+        Self::new(width, height, self.depth, pixels)
+    }
+
+    /// Halve the width and height of each slice with a box filter, for generating the next level
+    /// of a mip chain. The depth is left unchanged, so each face of a cubemap is downsampled
+    /// separately. A width or height of 0 stays 0.
+    pub fn downsampled(&self) -> Self {
+        let halved = |size: u32| if size == 0 { 0 } else { (size / 2).max(1) };
+        let width = halved(self.width);
+        let height = halved(self.height);
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * self.depth as usize);
+
+        for z in 0..self.depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let mut sum = [0.0; 4];
+                    let mut count = 0.0;
+
+                    // With odd dimensions, the last row and column are folded into the
+                    // texels before them.
+                    let y_end = if y == height - 1 {
+                        self.height
+                    } else {
+                        y * 2 + 2
+                    };
+                    let x_end = if x == width - 1 {
+                        self.width
+                    } else {
+                        x * 2 + 2
+                    };
+
+                    for source_y in (y * 2).min(self.height - 1)..y_end {
+                        for source_x in (x * 2).min(self.width - 1)..x_end {
+                            let pixel = self.pixels[self.index(source_x, source_y, z)];
+                            for (sum, value) in sum.iter_mut().zip(pixel) {
+                                *sum += value;
+                            }
+                            count += 1.0;
+                        }
+                    }
+
+                    pixels.push(sum.map(|sum| sum / count));
+                }
+            }
+        }
+
+        Self::new(width, height, self.depth, pixels)
+    }
+
+    /// Upload the image to a [`wgpu::TextureFormat::Rgba32Float`] texture that can be bound to
+    /// [`Compressor2D`](crate::Compressor2D) or, for images with a depth, to
+    /// [`Compressor3D`](crate::Compressor3D).
+    pub fn create_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: Option<&str>,
+    ) -> wgpu::Texture {
+        device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label,
+                size: self.extent(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: if self.is_3d() {
+                    wgpu::TextureDimension::D3
+                } else {
+                    wgpu::TextureDimension::D2
+                },
+                format: wgpu::TextureFormat::Rgba32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST,
+            },
+            bytemuck::cast_slice(&self.pixels),
+        )
+    }
+
+    fn index(&self, x: u32, y: u32, z: u32) -> usize {
+        (z as usize * self.height as usize + y as usize) * self.width as usize + x as usize
+    }
+}
+
+fn round_up_to_block(num: u32) -> u32 {
+    num.div_ceil(4) * 4
+}
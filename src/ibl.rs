@@ -0,0 +1,485 @@
+//! Prefiltering environment maps for image-based lighting, built with the `ibl` feature.
+
+use crate::{
+    dispatch_count, sanitization::Sanitization, CompressionParams, Compressor3D, Quality,
+    TextureParams,
+};
+use wgpu::util::DeviceExt;
+
+/// Prefilters a cubemap with the GGX distribution, producing the mip chain of a specular
+/// environment map where each level matches an increasing roughness.
+///
+/// Cubemaps are read and written as the slices of [`wgpu::TextureFormat::Rgba32Float`] 3D
+/// textures in the +X, -X, +Y, -Y, +Z, -Z order, as produced by
+/// [`EquirectToCubemap`](crate::equirect::EquirectToCubemap) and expected by [`Compressor3D`].
+pub struct SpecularPrefilter {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl SpecularPrefilter {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader =
+            device.create_shader_module(&wgpu::include_wgsl!("../shaders/ggx_prefilter.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression ggx prefilter bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression ggx prefilter pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("wgpu-bc6h-compression ggx prefilter pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    /// Create a cubemap with `face_size` by `face_size` faces and record the prefiltering of the
+    /// source into it for a single roughness. The texture always has the `TEXTURE_BINDING` and
+    /// `STORAGE_BINDING` usages.
+    pub fn prefilter(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &PrefilterParams,
+        texture_params: &TextureParams,
+    ) -> wgpu::Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: texture_params.label,
+            size: cubemap_extent(params.face_size),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: texture_params.usage
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING,
+        });
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let constants = [
+            params.face_size,
+            params.sample_count,
+            params.roughness.to_bits(),
+            0,
+        ];
+
+        let constant_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::bytes_of(&constants),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: params.bind_group_label,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(params.texture),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: constant_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass =
+            command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch(
+            dispatch_count(params.face_size, 8),
+            dispatch_count(params.face_size, 8),
+            6,
+        );
+
+        texture
+    }
+
+    /// Record the prefiltering of every level of a mip chain and their compression to BC6H,
+    /// returning a buffer of blocks for each level. Roughness increases linearly from 0 at the
+    /// first level to 1 at the last.
+    pub fn compress_mip_chain(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        compressor: &Compressor3D,
+        params: &MipChainParams,
+    ) -> Vec<PrefilteredLevel> {
+        let level_count = params.level_count.min(max_level_count(params.face_size));
+
+        (0..level_count)
+            .map(|level| {
+                let face_size = params.face_size >> level;
+
+                let roughness = if level_count > 1 {
+                    level as f32 / (level_count - 1) as f32
+                } else {
+                    0.0
+                };
+
+                let texture = self.prefilter(
+                    device,
+                    command_encoder,
+                    &PrefilterParams {
+                        bind_group_label: params.bind_group_label,
+                        texture: params.texture,
+                        face_size,
+                        roughness,
+                        sample_count: params.sample_count,
+                    },
+                    &TextureParams {
+                        label: Some("prefiltered cubemap"),
+                        usage: wgpu::TextureUsages::empty(),
+                    },
+                );
+
+                let extent = cubemap_extent(face_size);
+
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("prefiltered blocks"),
+                    size: extent.width as u64 * extent.height as u64 * 6,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                });
+
+                compressor.compress_to_buffer(
+                    device,
+                    command_encoder,
+                    &CompressionParams {
+                        bind_group_label: params.bind_group_label,
+                        texture: &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                        sampler: params.sampler,
+                        extent,
+                        quality: params.quality,
+                        source_mip_level: 0,
+                        source_base_layer: 0,
+                        profiler: None,
+                        sanitization: Sanitization::default(),
+                        corrected_texel_counter: None,
+                    },
+                    &buffer,
+                );
+
+                PrefilteredLevel {
+                    extent,
+                    roughness,
+                    buffer,
+                }
+            })
+            .collect()
+    }
+}
+
+pub struct PrefilterParams<'a> {
+    pub bind_group_label: Option<&'a str>,
+    /// The source cubemap.
+    pub texture: &'a wgpu::TextureView,
+    pub face_size: u32,
+    /// The perceptual roughness, from 0 to 1. This is squared to get the GGX alpha.
+    pub roughness: f32,
+    /// The number of importance samples per texel. Rougher levels need more samples to avoid
+    /// noise, with 1024 being a reasonable default.
+    pub sample_count: u32,
+}
+
+pub struct MipChainParams<'a> {
+    pub bind_group_label: Option<&'a str>,
+    /// The source cubemap.
+    pub texture: &'a wgpu::TextureView,
+    pub sampler: &'a wgpu::Sampler,
+    /// The face size of the first level. This needs to be a power of two so that every level
+    /// can be compressed.
+    pub face_size: u32,
+    /// The number of levels to generate, which is capped to stop at 4x4 faces as smaller levels
+    /// can't be compressed.
+    pub level_count: u32,
+    pub sample_count: u32,
+    pub quality: Quality,
+}
+
+/// The compressed blocks of one level of a prefiltered mip chain, with the faces stored one
+/// after the other.
+pub struct PrefilteredLevel {
+    pub extent: wgpu::Extent3d,
+    pub roughness: f32,
+    pub buffer: wgpu::Buffer,
+}
+
+/// The number of levels from `face_size` down to 4x4 faces.
+pub fn max_level_count(face_size: u32) -> u32 {
+    (face_size / 4).max(1).ilog2() + 1
+}
+
+fn cubemap_extent(face_size: u32) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: face_size,
+        height: face_size,
+        depth_or_array_layers: 6,
+    }
+}
+
+/// Projects a cubemap onto 9 spherical harmonics coefficients (bands 0 to 2) on the GPU, from
+/// which its diffuse irradiance can be reconstructed with [`Sh9::irradiance`].
+///
+/// The cubemap is read in the same layout as [`Compressor3D`] takes, so the same texture view
+/// can be compressed and projected in one go with [`ShProjector::compress_with_coefficients`].
+pub struct ShProjector {
+    pub texels_pipeline: wgpu::ComputePipeline,
+    pub partials_pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ShProjector {
+    /// The size of the buffer that the coefficients are written to, as 9 `[f32; 4]`s with the
+    /// last component unused.
+    pub const COEFFICIENTS_SIZE: u64 = 9 * 16;
+
+    /// The width and height of the tile of each face that is summed by a single workgroup.
+    const TILE_SIZE: u32 = 64;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader =
+            device.create_shader_module(&wgpu::include_wgsl!("../shaders/sh9_irradiance.wgsl"));
+
+        let storage_buffer_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression sh9 bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                storage_buffer_entry(1),
+                storage_buffer_entry(2),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression sh9 pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let texels_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("wgpu-bc6h-compression sh9 texels pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "reduce_texels",
+        });
+
+        let partials_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("wgpu-bc6h-compression sh9 partials pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "reduce_partials",
+        });
+
+        Self {
+            texels_pipeline,
+            partials_pipeline,
+            bind_group_layout,
+        }
+    }
+
+    /// Record the projection of a cubemap, writing the coefficients to a storage buffer of at
+    /// least [`ShProjector::COEFFICIENTS_SIZE`] bytes.
+    pub fn project_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &ShParams,
+        buffer: &wgpu::Buffer,
+    ) {
+        let tiles = dispatch_count(params.face_size, Self::TILE_SIZE);
+
+        let partials_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sh9 partial sums"),
+            size: tiles as u64 * tiles as u64 * 6 * Self::COEFFICIENTS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: params.bind_group_label,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(params.texture),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: partials_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass =
+            command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.set_pipeline(&self.texels_pipeline);
+        compute_pass.dispatch(tiles, tiles, 6);
+        compute_pass.set_pipeline(&self.partials_pipeline);
+        compute_pass.dispatch(1, 1, 1);
+    }
+
+    /// Record both the compression of a cubemap to BC6H blocks and its projection onto
+    /// spherical harmonics, so that a sky's texture and diffuse lighting come from one
+    /// submission.
+    pub fn compress_with_coefficients(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        compressor: &Compressor3D,
+        params: &CompressionParams,
+        blocks_buffer: &wgpu::Buffer,
+        coefficients_buffer: &wgpu::Buffer,
+    ) {
+        debug_assert_eq!(params.extent.width, params.extent.height);
+        debug_assert_eq!(params.extent.depth_or_array_layers, 6);
+
+        compressor.compress_to_buffer(device, command_encoder, params, blocks_buffer);
+
+        self.project_to_buffer(
+            device,
+            command_encoder,
+            &ShParams {
+                bind_group_label: params.bind_group_label,
+                texture: params.texture,
+                face_size: params.extent.width,
+            },
+            coefficients_buffer,
+        );
+    }
+}
+
+pub struct ShParams<'a> {
+    pub bind_group_label: Option<&'a str>,
+    /// The source cubemap.
+    pub texture: &'a wgpu::TextureView,
+    pub face_size: u32,
+}
+
+/// The radiance of an environment projected onto the first 9 real spherical harmonics, with an
+/// RGB value per coefficient.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sh9 {
+    pub coefficients: [[f32; 3]; 9],
+}
+
+impl Sh9 {
+    /// Read the coefficients from the bytes of the buffer written by [`ShProjector`].
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let value =
+            |index: usize| f32::from_le_bytes(bytes[index * 4..index * 4 + 4].try_into().unwrap());
+
+        let mut coefficients = [[0.0; 3]; 9];
+        for (i, coefficient) in coefficients.iter_mut().enumerate() {
+            *coefficient = [value(i * 4), value(i * 4 + 1), value(i * 4 + 2)];
+        }
+
+        Self { coefficients }
+    }
+
+    /// The diffuse irradiance arriving at a surface facing a normalized direction, using the
+    /// cosine lobe convolution from Ramamoorthi and Hanrahan's "An Efficient Representation
+    /// for Irradiance Environment Maps". Divide by pi for the outgoing radiance of a white
+    /// Lambertian surface.
+    pub fn irradiance(&self, direction: [f32; 3]) -> [f32; 3] {
+        use std::f32::consts::PI;
+
+        let [x, y, z] = direction;
+
+        let basis = [
+            0.282095 * PI,
+            0.488603 * y * (2.0 * PI / 3.0),
+            0.488603 * z * (2.0 * PI / 3.0),
+            0.488603 * x * (2.0 * PI / 3.0),
+            1.092548 * x * y * (PI / 4.0),
+            1.092548 * y * z * (PI / 4.0),
+            0.315392 * (3.0 * z * z - 1.0) * (PI / 4.0),
+            1.092548 * x * z * (PI / 4.0),
+            0.546274 * (x * x - y * y) * (PI / 4.0),
+        ];
+
+        let mut irradiance = [0.0; 3];
+        for (coefficient, basis) in self.coefficients.iter().zip(basis) {
+            for (irradiance, value) in irradiance.iter_mut().zip(coefficient) {
+                *irradiance += value * basis;
+            }
+        }
+
+        irradiance.map(|value: f32| value.max(0.0))
+    }
+}
@@ -0,0 +1,242 @@
+//! Compressing a texture a few rows of blocks at a time, so that the work can be spread over
+//! several frames.
+
+use std::ops::Range;
+
+use crate::{CompressionParams, Compressor2D, Compressor3D, TextureParams};
+
+/// The compressors that [`IncrementalCompressor`] can drive.
+pub trait RowCompressor {
+    /// The dimension of the textures that the compressor reads.
+    const DIMENSION: wgpu::TextureDimension;
+
+    fn compress_rows_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        buffer: &wgpu::Buffer,
+        rows: Range<u32>,
+    );
+}
+
+impl RowCompressor for Compressor2D {
+    const DIMENSION: wgpu::TextureDimension = wgpu::TextureDimension::D2;
+
+    fn compress_rows_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        buffer: &wgpu::Buffer,
+        rows: Range<u32>,
+    ) {
+        Compressor2D::compress_rows_to_buffer(self, device, command_encoder, params, buffer, rows)
+    }
+}
+
+impl RowCompressor for Compressor3D {
+    const DIMENSION: wgpu::TextureDimension = wgpu::TextureDimension::D3;
+
+    fn compress_rows_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        buffer: &wgpu::Buffer,
+        rows: Range<u32>,
+    ) {
+        Compressor3D::compress_rows_to_buffer(self, device, command_encoder, params, buffer, rows)
+    }
+}
+
+/// How much of the texture an [`IncrementalCompressor`] has compressed so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub compressed_blocks: u64,
+    pub total_blocks: u64,
+}
+
+impl Progress {
+    pub fn is_complete(&self) -> bool {
+        self.compressed_blocks == self.total_blocks
+    }
+
+    /// Between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total_blocks == 0 {
+            1.0
+        } else {
+            self.compressed_blocks as f32 / self.total_blocks as f32
+        }
+    }
+}
+
+/// Compresses a texture into a BC6H texture over several calls to [`step`](Self::step), each
+/// limited to a budget of blocks, such as once per frame for a runtime-captured reflection probe.
+///
+/// Each step copies the blocks it compressed into [`texture`](Self::texture), so it is fully
+/// written once [`Progress::is_complete`] returns true and the last step's commands have been
+/// submitted. The source texture must not change in between steps.
+pub struct IncrementalCompressor {
+    buffer: wgpu::Buffer,
+    texture: wgpu::Texture,
+    extent: wgpu::Extent3d,
+    dimension: wgpu::TextureDimension,
+    next_row: u32,
+}
+
+impl IncrementalCompressor {
+    /// Creates the target texture and the buffer that the blocks are compressed into. `dimension`
+    /// has to match the compressor that is passed to `step`.
+    pub fn new(
+        device: &wgpu::Device,
+        extent: wgpu::Extent3d,
+        dimension: wgpu::TextureDimension,
+        texture_params: &TextureParams,
+    ) -> Self {
+        debug_assert_eq!(extent.width % 4, 0);
+        debug_assert_eq!(extent.height % 4, 0);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: extent.width as u64 * extent.height as u64 * extent.depth_or_array_layers as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: texture_params.label,
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension,
+            format: wgpu::TextureFormat::Bc6hRgbUfloat,
+            usage: texture_params.usage | wgpu::TextureUsages::COPY_DST,
+        });
+
+        Self {
+            buffer,
+            texture,
+            extent,
+            dimension,
+            next_row: 0,
+        }
+    }
+
+    /// Compresses up to `block_budget` more blocks, rounded to whole rows of blocks and at least
+    /// one row, and copies them into the texture. Does nothing once the texture is complete.
+    pub fn step<C: RowCompressor>(
+        &mut self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        compressor: &C,
+        params: &CompressionParams,
+        block_budget: u32,
+    ) -> Progress {
+        debug_assert_eq!(C::DIMENSION, self.dimension);
+        debug_assert_eq!(params.extent, self.extent);
+
+        let width_in_blocks = self.extent.width / 4;
+        let row_count = (block_budget / width_in_blocks.max(1))
+            .max(1)
+            .min(self.total_rows() - self.next_row);
+
+        if row_count > 0 {
+            let rows = self.next_row..self.next_row + row_count;
+
+            compressor.compress_rows_to_buffer(
+                device,
+                command_encoder,
+                params,
+                &self.buffer,
+                rows.clone(),
+            );
+            self.copy_rows_to_texture(command_encoder, rows.clone());
+
+            self.next_row = rows.end;
+        }
+
+        self.progress()
+    }
+
+    pub fn progress(&self) -> Progress {
+        let width_in_blocks = (self.extent.width / 4) as u64;
+
+        Progress {
+            compressed_blocks: self.next_row as u64 * width_in_blocks,
+            total_blocks: self.total_rows() as u64 * width_in_blocks,
+        }
+    }
+
+    /// Starts over, for when the source texture has changed.
+    pub fn restart(&mut self) {
+        self.next_row = 0;
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// The compressed blocks, laid out as by `compress_to_buffer`.
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn into_texture(self) -> wgpu::Texture {
+        self.texture
+    }
+
+    // Rows of blocks, counted across all the slices.
+    fn total_rows(&self) -> u32 {
+        self.extent.height / 4 * self.extent.depth_or_array_layers
+    }
+
+    fn copy_rows_to_texture(&self, command_encoder: &mut wgpu::CommandEncoder, rows: Range<u32>) {
+        let width_in_blocks = self.extent.width / 4;
+        let height_in_blocks = self.extent.height / 4;
+
+        let mut row = rows.start;
+
+        // Whole slices are copied together, partial ones on their own.
+        while row < rows.end {
+            let slice = row / height_in_blocks;
+            let row_in_slice = row % height_in_blocks;
+
+            let (row_count, slice_count) =
+                if row_in_slice == 0 && rows.end - row >= height_in_blocks {
+                    (height_in_blocks, (rows.end - row) / height_in_blocks)
+                } else {
+                    (rows.end.min((slice + 1) * height_in_blocks) - row, 1)
+                };
+
+            command_encoder.copy_buffer_to_texture(
+                wgpu::ImageCopyBuffer {
+                    buffer: &self.buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: row as u64 * width_in_blocks as u64 * 16,
+                        bytes_per_row: std::num::NonZeroU32::new(width_in_blocks * 16),
+                        rows_per_image: std::num::NonZeroU32::new(height_in_blocks),
+                    },
+                },
+                wgpu::ImageCopyTexture {
+                    texture: &self.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: row_in_slice * 4,
+                        z: slice,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width: self.extent.width,
+                    height: row_count * 4,
+                    depth_or_array_layers: slice_count,
+                },
+            );
+
+            row += row_count * slice_count;
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Compressing textures whose size is only known on the GPU, such as dynamic-resolution probes.
+
+use wgpu::util::DeviceExt;
+
+use crate::{dispatch_count, CompressionParams, Quality};
+
+/// The extent that `compress_to_buffer_indirect` reads from a storage buffer, as written by an
+/// earlier pass. In WGSL, it is a struct of three `u32`s.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IndirectExtent {
+    /// The width of the texture in texels, divided by 4.
+    pub width_in_blocks: u32,
+    /// The height of the texture in texels, divided by 4.
+    pub height_in_blocks: u32,
+    /// The number of slices, which is ignored by
+    /// [`Compressor2D`](crate::Compressor2D).
+    pub depth: u32,
+}
+
+// The size of the arguments buffer: the compressor's constants, then the `dispatch_indirect`
+// arguments.
+const ARGUMENTS_SIZE: u64 = 44;
+const CONSTANTS_SIZE: u64 = 32;
+
+/// The pipelines that a compressor creates the first time it is asked to compress indirectly.
+pub(crate) struct IndirectPipelines {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    /// The compressor's pipeline with its constants in a uniform buffer, if its own takes push
+    /// constants. The constants can then come from the arguments buffer.
+    pub(crate) uniform_constants: Option<(wgpu::BindGroupLayout, wgpu::ComputePipeline)>,
+}
+
+/// How to dispatch one of the compressors.
+pub(crate) struct Compressor<'a> {
+    pub(crate) bind_group_layout: &'a wgpu::BindGroupLayout,
+    pub(crate) pipeline: &'a wgpu::ComputePipeline,
+    pub(crate) placeholder_counter: &'a wgpu::Buffer,
+    /// The constants, apart from the size, which is filled in from the extent.
+    pub(crate) constants: [u32; 8],
+    pub(crate) dimensions: u32,
+    pub(crate) group_size: [u32; 3],
+}
+
+impl IndirectPipelines {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        uniform_constants: Option<(wgpu::BindGroupLayout, wgpu::ComputePipeline)>,
+    ) -> Self {
+        let shader =
+            device.create_shader_module(&wgpu::include_wgsl!("../shaders/indirect_arguments.wgsl"));
+
+        let buffer = |binding, ty, min_binding_size| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: std::num::NonZeroU64::new(min_binding_size),
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression indirect arguments bind group layout"),
+            entries: &[
+                buffer(0, wgpu::BufferBindingType::Storage { read_only: true }, 12),
+                buffer(
+                    1,
+                    wgpu::BufferBindingType::Storage { read_only: false },
+                    ARGUMENTS_SIZE,
+                ),
+                buffer(2, wgpu::BufferBindingType::Uniform, 32),
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression indirect arguments pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("wgpu-bc6h-compression indirect arguments pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            uniform_constants,
+        }
+    }
+
+    /// Fills in the constants and dispatch size from the extent in a pass of its own, then
+    /// dispatches the compressor with them.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compress_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        compressor: Compressor,
+        params: &CompressionParams,
+        extent_buffer: &wgpu::Buffer,
+        extent_offset: wgpu::BufferAddress,
+        buffer: &wgpu::Buffer,
+    ) {
+        let max_size = [
+            params.extent.width / 4,
+            params.extent.height / 4,
+            params.extent.depth_or_array_layers,
+        ];
+        debug_assert_eq!(params.extent.width % 4, 0);
+        debug_assert_eq!(params.extent.height % 4, 0);
+        debug_assert_ne!(
+            params.quality,
+            Quality::High,
+            "the dispatch size is worked out one thread to a block"
+        );
+
+        // Without tiles, the largest extent has to fit in one dispatch.
+        let max_workgroups = device.limits().max_compute_workgroups_per_dimension;
+        debug_assert!(max_size
+            .iter()
+            .zip(compressor.group_size)
+            .all(|(&size, group_size)| dispatch_count(size, group_size) <= max_workgroups));
+
+        let mut arguments = [0; (ARGUMENTS_SIZE / 4) as usize];
+        arguments[..8].copy_from_slice(&compressor.constants);
+
+        let arguments_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("wgpu-bc6h-compression indirect arguments"),
+            contents: bytemuck::cast_slice(&arguments),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::UNIFORM
+                | wgpu::BufferUsages::INDIRECT,
+        });
+
+        let parameters = [
+            max_size[0],
+            max_size[1],
+            max_size[2],
+            compressor.dimensions,
+            compressor.group_size[0],
+            compressor.group_size[1],
+            compressor.group_size[2],
+            0,
+        ];
+
+        let parameter_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::bytes_of(&parameters),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let arguments_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("wgpu-bc6h-compression indirect arguments bind group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: extent_buffer,
+                        offset: extent_offset,
+                        size: std::num::NonZeroU64::new(12),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: arguments_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: parameter_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: params.bind_group_label,
+            layout: compressor.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(params.texture),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(params.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &arguments_buffer,
+                        offset: 0,
+                        size: std::num::NonZeroU64::new(CONSTANTS_SIZE),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: params
+                        .corrected_texel_counter
+                        .map_or(compressor.placeholder_counter, |counter| &counter.buffer)
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &arguments_bind_group, &[]);
+            compute_pass.dispatch(1, 1, 1);
+        }
+
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            compute_pass.set_pipeline(compressor.pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_indirect(&arguments_buffer, CONSTANTS_SIZE);
+        }
+    }
+}
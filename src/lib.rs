@@ -1,73 +1,107 @@
-#[cfg(not(feature = "push_constants"))]
 use wgpu::util::DeviceExt;
 
+pub mod batch;
+pub mod decode;
+pub mod equirect;
+mod hdr_image;
+#[cfg(feature = "ibl")]
+pub mod ibl;
+pub mod incremental;
+pub mod indirect;
+#[cfg(feature = "exr")]
+pub mod openexr;
+pub mod pfm;
+pub mod profiling;
+pub mod radiance;
+pub mod requirements;
+pub mod sanitization;
+mod wgsl;
+
+pub use hdr_image::HdrImage;
+
 pub struct Compressor2D {
     pub pipeline: wgpu::ComputePipeline,
     pub bind_group_layout: wgpu::BindGroupLayout,
+    // Bound in place of a `CorrectedTexelCounter` when none is passed.
+    placeholder_counter: wgpu::Buffer,
+    push_constants: bool,
+    // Created by the first `compress_to_buffer_indirect`.
+    indirect: std::sync::OnceLock<indirect::IndirectPipelines>,
+    // Created by the first compression with `Quality::High`.
+    cooperative_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
+    // Created by the first compression with a `CorrectedTexelCounter`, if `pipeline` is compiled
+    // from SPIR-V.
+    counting_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
 }
 
 impl Compressor2D {
+    /// Like [`new`](Self::new), but checks the device against the
+    /// [`DeviceRequirements`](requirements::DeviceRequirements) first, instead of failing when
+    /// creating the pipeline or dispatching.
+    pub fn try_new(device: &wgpu::Device) -> Result<Self, requirements::RequirementsError> {
+        requirements::DeviceRequirements::new(&requirements::Configuration {
+            compressed_textures: false,
+            push_constants: requirements::can_use_push_constants(device),
+        })
+        .check(device)?;
+
+        Ok(Self::new(device))
+    }
+
+    /// Uses push constants for the constants if the device has
+    /// [`wgpu::Features::PUSH_CONSTANTS`] and a large enough `max_push_constant_size`, or a
+    /// uniform buffer otherwise.
     pub fn new(device: &wgpu::Device) -> Self {
-        #[cfg(feature = "push_constants")]
-        let shader_bytes = wgpu::include_spirv!("../shaders/compiled/2d_push_constants.comp.spv");
-        #[cfg(not(feature = "push_constants"))]
-        let shader_bytes = wgpu::include_spirv!("../shaders/compiled/2d.comp.spv");
+        let push_constants = requirements::can_use_push_constants(device);
+        let (bind_group_layout, pipeline) = Self::create_pipeline(device, push_constants);
 
-        let shader = device.create_shader_module(&shader_bytes);
+        Self {
+            bind_group_layout,
+            pipeline,
+            placeholder_counter: placeholder_counter(device),
+            push_constants,
+            indirect: std::sync::OnceLock::new(),
+            cooperative_pipeline: std::sync::OnceLock::new(),
+            counting_pipeline: std::sync::OnceLock::new(),
+        }
+    }
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("wgpu-bc6h-compression 2d bind group layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                #[cfg(not(feature = "push_constants"))]
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
+    fn create_pipeline(
+        device: &wgpu::Device,
+        push_constants: bool,
+    ) -> (wgpu::BindGroupLayout, wgpu::ComputePipeline) {
+        // Browsers only accept WGSL, so the shaders are used there as they are instead of the
+        // SPIR-V generated from them.
+        #[cfg(any(feature = "wgsl", target_arch = "wasm32"))]
+        let shader_descriptor = wgsl::shader_module_descriptor(
+            "wgpu-bc6h-compression 2d shader",
+            include_str!("../shaders/compress_2d.wgsl"),
+            push_constants,
+        );
+        #[cfg(not(any(feature = "wgsl", target_arch = "wasm32")))]
+        let shader_descriptor = if push_constants {
+            wgpu::include_spirv!(concat!(
+                env!("BC6H_SPIRV_DIR"),
+                "/2d_push_constants.comp.spv"
+            ))
+        } else {
+            wgpu::include_spirv!(concat!(env!("BC6H_SPIRV_DIR"), "/2d.comp.spv"))
+        };
+
+        let shader = device.create_shader_module(&shader_descriptor);
+        let bind_group_layout = Self::create_bind_group_layout(device, push_constants);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("wgpu-bc6h-compression 2d pipeline layout"),
             bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[
-                #[cfg(feature = "push_constants")]
-                wgpu::PushConstantRange {
+            push_constant_ranges: if push_constants {
+                &[wgpu::PushConstantRange {
                     stages: wgpu::ShaderStages::COMPUTE,
-                    range: 0..std::mem::size_of::<[u32; 2]>() as u32,
-                },
-            ],
+                    range: 0..requirements::PUSH_CONSTANT_SIZE,
+                }]
+            } else {
+                &[]
+            },
         });
 
         let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
@@ -77,10 +111,69 @@ impl Compressor2D {
             entry_point: "main",
         });
 
-        Self {
-            bind_group_layout,
-            pipeline,
+        (bind_group_layout, pipeline)
+    }
+
+    fn create_bind_group_layout(
+        device: &wgpu::Device,
+        push_constants: bool,
+    ) -> wgpu::BindGroupLayout {
+        let mut layout_entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: std::num::NonZeroU64::new(4),
+                },
+                count: None,
+            },
+        ];
+
+        if !push_constants {
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
         }
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression 2d bind group layout"),
+            entries: &layout_entries,
+        })
     }
 
     pub fn compress_to_buffer(
@@ -89,26 +182,129 @@ impl Compressor2D {
         command_encoder: &mut wgpu::CommandEncoder,
         params: &CompressionParams,
         buffer: &wgpu::Buffer,
+    ) {
+        let height_in_blocks = params.extent.height / 4;
+
+        self.compress_rows_to_buffer(device, command_encoder, params, buffer, 0..height_in_blocks);
+    }
+
+    /// Like [`compress_to_buffer`](Self::compress_to_buffer), but for a texture whose size is
+    /// only known on the GPU. The size is read from an [`IndirectExtent`](indirect::IndirectExtent)
+    /// at `extent_offset` in `extent_buffer`, which needs [`wgpu::BufferUsages::STORAGE`], and is
+    /// turned into the constants and the size of a `dispatch_indirect` without reading it back.
+    ///
+    /// `params.extent` is the largest size that the texture can have, which the extent is clamped
+    /// to. `buffer` is laid out for the actual size, and has to be large enough for the largest.
+    /// `params.profiler` is ignored, as the number of blocks isn't known on the CPU, and
+    /// `params.quality` can't be [`Quality::High`].
+    pub fn compress_to_buffer_indirect(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        extent_buffer: &wgpu::Buffer,
+        extent_offset: wgpu::BufferAddress,
+        buffer: &wgpu::Buffer,
+    ) {
+        let indirect = self.indirect.get_or_init(|| {
+            indirect::IndirectPipelines::new(
+                device,
+                self.push_constants.then(|| {
+                    // From the WGSL port, which can count corrected texels on every build.
+                    let bind_group_layout = Self::create_bind_group_layout(device, false);
+                    let pipeline = create_wgsl_pipeline(
+                        device,
+                        "wgpu-bc6h-compression 2d indirect",
+                        include_str!("../shaders/compress_2d.wgsl"),
+                        "main",
+                        &bind_group_layout,
+                        false,
+                    );
+
+                    (bind_group_layout, pipeline)
+                }),
+            )
+        });
+
+        let (bind_group_layout, pipeline) = match &indirect.uniform_constants {
+            Some((bind_group_layout, pipeline)) => (bind_group_layout, pipeline),
+            None => (&self.bind_group_layout, self.pipeline_for(device, params)),
+        };
+
+        indirect.compress_to_buffer(
+            device,
+            command_encoder,
+            indirect::Compressor {
+                bind_group_layout,
+                pipeline,
+                placeholder_counter: &self.placeholder_counter,
+                constants: [0, 0, params.flags(), 0, 0, params.source_mip_level, 0, 0],
+                dimensions: 2,
+                group_size: [8, 8, 1],
+            },
+            params,
+            extent_buffer,
+            extent_offset,
+            buffer,
+        );
+    }
+
+    /// Only compresses the given rows of blocks, writing them to the same place in `buffer` as
+    /// `compress_to_buffer` would.
+    pub fn compress_rows_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        buffer: &wgpu::Buffer,
+        rows: std::ops::Range<u32>,
     ) {
         let width_in_blocks = params.extent.width / 4;
         let height_in_blocks = params.extent.height / 4;
         debug_assert_eq!(params.extent.width % 4, 0);
         debug_assert_eq!(params.extent.height % 4, 0);
         debug_assert_eq!(params.extent.depth_or_array_layers, 1);
+        debug_assert_eq!(params.source_base_layer, 0);
 
-        let constants = [width_in_blocks, height_in_blocks];
+        // With `Quality::High`, a workgroup encodes each block instead of a thread.
+        let cooperative = params.quality == Quality::High;
+        let (pipeline, group_size) = if cooperative {
+            (self.cooperative_pipeline(device), [1, 1])
+        } else {
+            (self.pipeline_for(device, params), [8, 8])
+        };
 
-        #[cfg(not(feature = "push_constants"))]
-        let compute_contant_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::bytes_of(&constants),
-            usage: wgpu::BufferUsages::UNIFORM,
-        });
+        let tiles: Vec<_> = tiles(
+            device,
+            width_in_blocks,
+            height_in_blocks,
+            rows.clone(),
+            if cooperative { [1, 1] } else { [8, 1] },
+        )
+        .into_iter()
+        .map(|tile| {
+            let binding = tile.binding(device, width_in_blocks, height_in_blocks);
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: params.bind_group_label,
-            layout: &self.bind_group_layout,
-            entries: &[
+            let constants = [
+                width_in_blocks,
+                tile.rows.end - tile.rows.start,
+                params.flags(),
+                tile.rows.start,
+                binding.index_offset,
+                params.source_mip_level,
+                0,
+                0,
+            ];
+
+            let compute_constant_buffer = (!self.push_constants).then(|| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: bytemuck::bytes_of(&constants),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                })
+            });
+
+            let mut entries = vec![
                 wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::TextureView(params.texture),
@@ -119,27 +315,106 @@ impl Compressor2D {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: buffer.as_entire_binding(),
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer,
+                        offset: binding.offset,
+                        size: std::num::NonZeroU64::new(binding.size),
+                    }),
                 },
-                #[cfg(not(feature = "push_constants"))]
                 wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: compute_contant_buffer.as_entire_binding(),
+                    binding: 4,
+                    resource: params
+                        .corrected_texel_counter
+                        .map_or(&self.placeholder_counter, |counter| &counter.buffer)
+                        .as_entire_binding(),
                 },
-            ],
+            ];
+
+            if let Some(compute_constant_buffer) = &compute_constant_buffer {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: compute_constant_buffer.as_entire_binding(),
+                });
+            }
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: params.bind_group_label,
+                layout: &self.bind_group_layout,
+                entries: &entries,
+            });
+
+            (tile, constants, bind_group)
+        })
+        .collect();
+
+        let timestamp = params.profiler.and_then(|profiler| {
+            profiler.begin_pass(
+                command_encoder,
+                (rows.end - rows.start) as u64 * width_in_blocks as u64,
+            )
         });
 
-        let mut compute_pass =
-            command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
-        compute_pass.set_pipeline(&self.pipeline);
-        compute_pass.set_bind_group(0, &bind_group, &[]);
-        #[cfg(feature = "push_constants")]
-        compute_pass.set_push_constants(0, bytemuck::bytes_of(&constants));
-        compute_pass.dispatch(
-            dispatch_count(width_in_blocks, 8),
-            dispatch_count(height_in_blocks, 8),
-            1,
-        );
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            compute_pass.set_pipeline(pipeline);
+
+            for (tile, constants, bind_group) in &tiles {
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                // Otherwise the constants are in the bind group.
+                if self.push_constants {
+                    compute_pass.set_push_constants(0, bytemuck::bytes_of(constants));
+                }
+                compute_pass.dispatch(
+                    dispatch_count(width_in_blocks, group_size[0]),
+                    dispatch_count(tile.rows.end - tile.rows.start, group_size[1]),
+                    1,
+                );
+            }
+        }
+
+        if let (Some(profiler), Some(index)) = (params.profiler, timestamp) {
+            profiler.end_pass(command_encoder, index);
+        }
+    }
+
+    fn cooperative_pipeline(&self, device: &wgpu::Device) -> &wgpu::ComputePipeline {
+        self.cooperative_pipeline.get_or_init(|| {
+            create_wgsl_pipeline(
+                device,
+                "wgpu-bc6h-compression 2d cooperative",
+                include_str!("../shaders/compress_2d.wgsl"),
+                "main_cooperative",
+                &self.bind_group_layout,
+                self.push_constants,
+            )
+        })
+    }
+
+    /// The compressor's own pipeline, unless it is compiled from SPIR-V and `params` has a
+    /// `CorrectedTexelCounter`. Counting needs atomics, which wgpu can't parse from SPIR-V, so
+    /// the WGSL port is used then.
+    fn pipeline_for(
+        &self,
+        device: &wgpu::Device,
+        params: &CompressionParams,
+    ) -> &wgpu::ComputePipeline {
+        if cfg!(any(feature = "wgsl", target_arch = "wasm32"))
+            || params.corrected_texel_counter.is_none()
+        {
+            return &self.pipeline;
+        }
+
+        self.counting_pipeline.get_or_init(|| {
+            create_wgsl_pipeline(
+                device,
+                "wgpu-bc6h-compression 2d counting",
+                include_str!("../shaders/compress_2d.wgsl"),
+                "main",
+                &self.bind_group_layout,
+                self.push_constants,
+            )
+        })
     }
 
     pub fn compress_to_texture(
@@ -149,15 +424,6 @@ impl Compressor2D {
         params: &CompressionParams,
         texture_params: &TextureParams,
     ) -> wgpu::Texture {
-        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: params.extent.width as u64 * params.extent.height as u64,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-
-        self.compress_to_buffer(device, command_encoder, params, &buffer);
-
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: texture_params.label,
             size: params.extent,
@@ -168,99 +434,128 @@ impl Compressor2D {
             usage: texture_params.usage | wgpu::TextureUsages::COPY_DST,
         });
 
-        command_encoder.copy_buffer_to_texture(
-            wgpu::ImageCopyBuffer {
-                buffer: &buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    // width / 4 (because a block contains 4 pixels horizontally) * 16 (the block size)
-                    // confusing, I know.
-                    bytes_per_row: Some(
-                        std::num::NonZeroU32::new(params.extent.width * 4).unwrap(),
-                    ),
-                    rows_per_image: Some(std::num::NonZeroU32::new(params.extent.height).unwrap()),
-                },
-            },
+        self.compress_into_texture(
+            device,
+            command_encoder,
+            params,
             wgpu::ImageCopyTexture {
                 texture: &texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            params.extent,
         );
 
         texture
     }
+
+    /// Compresses into part of an existing [`wgpu::TextureFormat::Bc6hRgbUfloat`] texture, such
+    /// as a level of a preallocated mip chain. The compressed region starts at
+    /// `destination.origin` and is `params.extent` in size. `destination.origin.z` is the array
+    /// layer.
+    pub fn compress_into_texture(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        destination: wgpu::ImageCopyTexture,
+    ) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: params.extent.width as u64 * params.extent.height as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        self.compress_to_buffer(device, command_encoder, params, &buffer);
+
+        copy_blocks_to_texture(command_encoder, &buffer, params.extent, destination);
+    }
 }
 
 pub struct Compressor3D {
     pub pipeline: wgpu::ComputePipeline,
     pub bind_group_layout: wgpu::BindGroupLayout,
+    // Bound in place of a `CorrectedTexelCounter` when none is passed.
+    placeholder_counter: wgpu::Buffer,
+    push_constants: bool,
+    // Created by the first `compress_to_buffer_indirect`.
+    indirect: std::sync::OnceLock<indirect::IndirectPipelines>,
+    // Created by the first compression with `Quality::High`.
+    cooperative_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
+    // Created by the first compression with a `CorrectedTexelCounter`, if `pipeline` is compiled
+    // from SPIR-V.
+    counting_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
 }
 
 impl Compressor3D {
+    /// Like [`new`](Self::new), but checks the device against the
+    /// [`DeviceRequirements`](requirements::DeviceRequirements) first, instead of failing when
+    /// creating the pipeline or dispatching.
+    pub fn try_new(device: &wgpu::Device) -> Result<Self, requirements::RequirementsError> {
+        requirements::DeviceRequirements::new(&requirements::Configuration {
+            compressed_textures: false,
+            push_constants: requirements::can_use_push_constants(device),
+        })
+        .check(device)?;
+
+        Ok(Self::new(device))
+    }
+
+    /// Uses push constants for the constants if the device has
+    /// [`wgpu::Features::PUSH_CONSTANTS`] and a large enough `max_push_constant_size`, or a
+    /// uniform buffer otherwise.
     pub fn new(device: &wgpu::Device) -> Self {
-        #[cfg(feature = "push_constants")]
-        let shader_bytes = wgpu::include_spirv!("../shaders/compiled/3d_push_constants.comp.spv");
-        #[cfg(not(feature = "push_constants"))]
-        let shader_bytes = wgpu::include_spirv!("../shaders/compiled/3d.comp.spv");
+        let push_constants = requirements::can_use_push_constants(device);
+        let (bind_group_layout, pipeline) = Self::create_pipeline(device, push_constants);
 
-        let shader = device.create_shader_module(&shader_bytes);
+        Self {
+            bind_group_layout,
+            pipeline,
+            placeholder_counter: placeholder_counter(device),
+            push_constants,
+            indirect: std::sync::OnceLock::new(),
+            cooperative_pipeline: std::sync::OnceLock::new(),
+            counting_pipeline: std::sync::OnceLock::new(),
+        }
+    }
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("wgpu-bc6h-compression 3d bind group layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                        view_dimension: wgpu::TextureViewDimension::D3,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                #[cfg(not(feature = "push_constants"))]
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
+    fn create_pipeline(
+        device: &wgpu::Device,
+        push_constants: bool,
+    ) -> (wgpu::BindGroupLayout, wgpu::ComputePipeline) {
+        // Browsers only accept WGSL, so the shaders are used there as they are instead of the
+        // SPIR-V generated from them.
+        #[cfg(any(feature = "wgsl", target_arch = "wasm32"))]
+        let shader_descriptor = wgsl::shader_module_descriptor(
+            "wgpu-bc6h-compression 3d shader",
+            include_str!("../shaders/compress_3d.wgsl"),
+            push_constants,
+        );
+        #[cfg(not(any(feature = "wgsl", target_arch = "wasm32")))]
+        let shader_descriptor = if push_constants {
+            wgpu::include_spirv!(concat!(
+                env!("BC6H_SPIRV_DIR"),
+                "/3d_push_constants.comp.spv"
+            ))
+        } else {
+            wgpu::include_spirv!(concat!(env!("BC6H_SPIRV_DIR"), "/3d.comp.spv"))
+        };
+
+        let shader = device.create_shader_module(&shader_descriptor);
+        let bind_group_layout = Self::create_bind_group_layout(device, push_constants);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("wgpu-bc6h-compression 3d pipeline layout"),
             bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[
-                #[cfg(feature = "push_constants")]
-                wgpu::PushConstantRange {
+            push_constant_ranges: if push_constants {
+                &[wgpu::PushConstantRange {
                     stages: wgpu::ShaderStages::COMPUTE,
-                    range: 0..std::mem::size_of::<[u32; 3]>() as u32,
-                },
-            ],
+                    range: 0..requirements::PUSH_CONSTANT_SIZE,
+                }]
+            } else {
+                &[]
+            },
         });
 
         let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
@@ -270,10 +565,69 @@ impl Compressor3D {
             entry_point: "main",
         });
 
-        Self {
-            bind_group_layout,
-            pipeline,
+        (bind_group_layout, pipeline)
+    }
+
+    fn create_bind_group_layout(
+        device: &wgpu::Device,
+        push_constants: bool,
+    ) -> wgpu::BindGroupLayout {
+        let mut layout_entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: std::num::NonZeroU64::new(4),
+                },
+                count: None,
+            },
+        ];
+
+        if !push_constants {
+            layout_entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
         }
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("wgpu-bc6h-compression 3d bind group layout"),
+            entries: &layout_entries,
+        })
     }
 
     pub fn compress_to_buffer(
@@ -282,26 +636,143 @@ impl Compressor3D {
         command_encoder: &mut wgpu::CommandEncoder,
         params: &CompressionParams,
         buffer: &wgpu::Buffer,
+    ) {
+        let height_in_blocks = params.extent.height / 4;
+        let depth = params.extent.depth_or_array_layers;
+
+        self.compress_rows_to_buffer(
+            device,
+            command_encoder,
+            params,
+            buffer,
+            0..height_in_blocks * depth,
+        );
+    }
+
+    /// Like [`compress_to_buffer`](Self::compress_to_buffer), but for a texture whose size is
+    /// only known on the GPU. The size is read from an [`IndirectExtent`](indirect::IndirectExtent)
+    /// at `extent_offset` in `extent_buffer`, which needs [`wgpu::BufferUsages::STORAGE`], and is
+    /// turned into the constants and the size of a `dispatch_indirect` without reading it back.
+    ///
+    /// `params.extent` is the largest size that the texture can have, which the extent is clamped
+    /// to. `buffer` is laid out for the actual size, and has to be large enough for the largest.
+    /// `params.profiler` is ignored, as the number of blocks isn't known on the CPU, and
+    /// `params.quality` can't be [`Quality::High`].
+    pub fn compress_to_buffer_indirect(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        extent_buffer: &wgpu::Buffer,
+        extent_offset: wgpu::BufferAddress,
+        buffer: &wgpu::Buffer,
+    ) {
+        let indirect = self.indirect.get_or_init(|| {
+            indirect::IndirectPipelines::new(
+                device,
+                self.push_constants.then(|| {
+                    // From the WGSL port, which can count corrected texels on every build.
+                    let bind_group_layout = Self::create_bind_group_layout(device, false);
+                    let pipeline = create_wgsl_pipeline(
+                        device,
+                        "wgpu-bc6h-compression 3d indirect",
+                        include_str!("../shaders/compress_3d.wgsl"),
+                        "main",
+                        &bind_group_layout,
+                        false,
+                    );
+
+                    (bind_group_layout, pipeline)
+                }),
+            )
+        });
+
+        let (bind_group_layout, pipeline) = match &indirect.uniform_constants {
+            Some((bind_group_layout, pipeline)) => (bind_group_layout, pipeline),
+            None => (&self.bind_group_layout, self.pipeline_for(device, params)),
+        };
+
+        indirect.compress_to_buffer(
+            device,
+            command_encoder,
+            indirect::Compressor {
+                bind_group_layout,
+                pipeline,
+                placeholder_counter: &self.placeholder_counter,
+                constants: [
+                    0,
+                    0,
+                    0,
+                    params.flags(),
+                    0,
+                    params.source_base_layer,
+                    0,
+                    params.source_mip_level,
+                ],
+                dimensions: 3,
+                group_size: [4, 4, 4],
+            },
+            params,
+            extent_buffer,
+            extent_offset,
+            buffer,
+        );
+    }
+
+    /// Only compresses the given rows of blocks, counted across all the slices, writing them to
+    /// the same place in `buffer` as `compress_to_buffer` would.
+    pub fn compress_rows_to_buffer(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        buffer: &wgpu::Buffer,
+        rows: std::ops::Range<u32>,
     ) {
         let width_in_blocks = params.extent.width / 4;
         let height_in_blocks = params.extent.height / 4;
         debug_assert_eq!(params.extent.width % 4, 0);
         debug_assert_eq!(params.extent.height % 4, 0);
-        let depth = params.extent.depth_or_array_layers;
 
-        let constants = [width_in_blocks, height_in_blocks, depth, 0];
+        // With `Quality::High`, a workgroup encodes each block instead of a thread.
+        let cooperative = params.quality == Quality::High;
+        let (pipeline, group_size) = if cooperative {
+            (self.cooperative_pipeline(device), 1)
+        } else {
+            (self.pipeline_for(device, params), 4)
+        };
 
-        #[cfg(not(feature = "push_constants"))]
-        let compute_contant_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::bytes_of(&constants),
-            usage: wgpu::BufferUsages::UNIFORM,
-        });
+        let tiles: Vec<_> = tiles(
+            device,
+            width_in_blocks,
+            height_in_blocks,
+            rows.clone(),
+            if cooperative { [1, 1] } else { [4, 4] },
+        )
+        .into_iter()
+        .map(|tile| {
+            let binding = tile.binding(device, width_in_blocks, height_in_blocks);
+
+            let constants = [
+                width_in_blocks,
+                tile.rows.end - tile.rows.start,
+                tile.slices.end - tile.slices.start,
+                params.flags(),
+                tile.rows.start,
+                params.source_base_layer + tile.slices.start,
+                binding.index_offset,
+                params.source_mip_level,
+            ];
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: params.bind_group_label,
-            layout: &self.bind_group_layout,
-            entries: &[
+            let compute_constant_buffer = (!self.push_constants).then(|| {
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: None,
+                    contents: bytemuck::bytes_of(&constants),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                })
+            });
+
+            let mut entries = vec![
                 wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::TextureView(params.texture),
@@ -312,27 +783,106 @@ impl Compressor3D {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: buffer.as_entire_binding(),
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer,
+                        offset: binding.offset,
+                        size: std::num::NonZeroU64::new(binding.size),
+                    }),
                 },
-                #[cfg(not(feature = "push_constants"))]
                 wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: compute_contant_buffer.as_entire_binding(),
+                    binding: 4,
+                    resource: params
+                        .corrected_texel_counter
+                        .map_or(&self.placeholder_counter, |counter| &counter.buffer)
+                        .as_entire_binding(),
                 },
-            ],
+            ];
+
+            if let Some(compute_constant_buffer) = &compute_constant_buffer {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: compute_constant_buffer.as_entire_binding(),
+                });
+            }
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: params.bind_group_label,
+                layout: &self.bind_group_layout,
+                entries: &entries,
+            });
+
+            (tile, constants, bind_group)
+        })
+        .collect();
+
+        let timestamp = params.profiler.and_then(|profiler| {
+            profiler.begin_pass(
+                command_encoder,
+                (rows.end - rows.start) as u64 * width_in_blocks as u64,
+            )
         });
 
-        let mut compute_pass =
-            command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
-        compute_pass.set_pipeline(&self.pipeline);
-        compute_pass.set_bind_group(0, &bind_group, &[]);
-        #[cfg(feature = "push_constants")]
-        compute_pass.set_push_constants(0, bytemuck::bytes_of(&constants));
-        compute_pass.dispatch(
-            dispatch_count(width_in_blocks, 4),
-            dispatch_count(height_in_blocks, 4),
-            dispatch_count(depth, 4),
-        );
+        {
+            let mut compute_pass =
+                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            compute_pass.set_pipeline(pipeline);
+
+            for (tile, constants, bind_group) in &tiles {
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                // Otherwise the constants are in the bind group.
+                if self.push_constants {
+                    compute_pass.set_push_constants(0, bytemuck::bytes_of(constants));
+                }
+                compute_pass.dispatch(
+                    dispatch_count(width_in_blocks, group_size),
+                    dispatch_count(tile.rows.end - tile.rows.start, group_size),
+                    dispatch_count(tile.slices.end - tile.slices.start, group_size),
+                );
+            }
+        }
+
+        if let (Some(profiler), Some(index)) = (params.profiler, timestamp) {
+            profiler.end_pass(command_encoder, index);
+        }
+    }
+
+    fn cooperative_pipeline(&self, device: &wgpu::Device) -> &wgpu::ComputePipeline {
+        self.cooperative_pipeline.get_or_init(|| {
+            create_wgsl_pipeline(
+                device,
+                "wgpu-bc6h-compression 3d cooperative",
+                include_str!("../shaders/compress_3d.wgsl"),
+                "main_cooperative",
+                &self.bind_group_layout,
+                self.push_constants,
+            )
+        })
+    }
+
+    /// The compressor's own pipeline, unless it is compiled from SPIR-V and `params` has a
+    /// `CorrectedTexelCounter`. Counting needs atomics, which wgpu can't parse from SPIR-V, so
+    /// the WGSL port is used then.
+    fn pipeline_for(
+        &self,
+        device: &wgpu::Device,
+        params: &CompressionParams,
+    ) -> &wgpu::ComputePipeline {
+        if cfg!(any(feature = "wgsl", target_arch = "wasm32"))
+            || params.corrected_texel_counter.is_none()
+        {
+            return &self.pipeline;
+        }
+
+        self.counting_pipeline.get_or_init(|| {
+            create_wgsl_pipeline(
+                device,
+                "wgpu-bc6h-compression 3d counting",
+                include_str!("../shaders/compress_3d.wgsl"),
+                "main",
+                &self.bind_group_layout,
+                self.push_constants,
+            )
+        })
     }
 
     pub fn compress_to_texture(
@@ -342,17 +892,6 @@ impl Compressor3D {
         params: &CompressionParams,
         texture_params: &TextureParams,
     ) -> wgpu::Texture {
-        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: params.extent.width as u64
-                * params.extent.height as u64
-                * params.extent.depth_or_array_layers as u64,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-
-        self.compress_to_buffer(device, command_encoder, params, &buffer);
-
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: texture_params.label,
             size: params.extent,
@@ -363,30 +902,46 @@ impl Compressor3D {
             usage: texture_params.usage | wgpu::TextureUsages::COPY_DST,
         });
 
-        command_encoder.copy_buffer_to_texture(
-            wgpu::ImageCopyBuffer {
-                buffer: &buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    // width / 4 (because a block contains 4 pixels horizontally) * 16 (the block size)
-                    // confusing, I know.
-                    bytes_per_row: Some(
-                        std::num::NonZeroU32::new(params.extent.width * 4).unwrap(),
-                    ),
-                    rows_per_image: Some(std::num::NonZeroU32::new(params.extent.height).unwrap()),
-                },
-            },
+        self.compress_into_texture(
+            device,
+            command_encoder,
+            params,
             wgpu::ImageCopyTexture {
                 texture: &texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            params.extent,
         );
 
         texture
     }
+
+    /// Compresses into part of an existing [`wgpu::TextureFormat::Bc6hRgbUfloat`] texture, such
+    /// as a level of a preallocated mip chain. The compressed region starts at
+    /// `destination.origin` and is `params.extent` in size. The slices are written from
+    /// `destination.origin.z`, which can be a slice of a 3D texture or a layer of a 2D array, such
+    /// as a cubemap.
+    pub fn compress_into_texture(
+        &self,
+        device: &wgpu::Device,
+        command_encoder: &mut wgpu::CommandEncoder,
+        params: &CompressionParams,
+        destination: wgpu::ImageCopyTexture,
+    ) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: params.extent.width as u64
+                * params.extent.height as u64
+                * params.extent.depth_or_array_layers as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        self.compress_to_buffer(device, command_encoder, params, &buffer);
+
+        copy_blocks_to_texture(command_encoder, &buffer, params.extent, destination);
+    }
 }
 
 pub struct CompressionParams<'a> {
@@ -394,6 +949,120 @@ pub struct CompressionParams<'a> {
     pub texture: &'a wgpu::TextureView,
     pub sampler: &'a wgpu::Sampler,
     pub extent: wgpu::Extent3d,
+    pub quality: Quality,
+    /// The mip level of `texture` to compress, which `extent` is the size of. Along with
+    /// `source_base_layer`, this lets one view of a whole texture be compressed a level or a few
+    /// layers at a time.
+    pub source_mip_level: u32,
+    /// The first slice of `texture` to compress with [`Compressor3D`], which compresses
+    /// `extent.depth_or_array_layers` of them from there. Arrays, such as cubemaps, are 3D
+    /// textures with a slice per layer. It has to be 0 for [`Compressor2D`].
+    pub source_base_layer: u32,
+    /// Records how long the compression takes on the GPU.
+    pub profiler: Option<&'a profiling::Profiler>,
+    pub sanitization: sanitization::Sanitization,
+    /// Counts the texels changed by `sanitization`.
+    pub corrected_texel_counter: Option<&'a sanitization::CorrectedTexelCounter>,
+}
+
+impl<'a> CompressionParams<'a> {
+    // The `Constants.Flags` of the shader.
+    fn flags(&self) -> u32 {
+        compression_flags(
+            self.quality,
+            self.sanitization,
+            self.corrected_texel_counter.is_some(),
+        )
+    }
+}
+
+fn compression_flags(
+    quality: Quality,
+    sanitization: sanitization::Sanitization,
+    count_corrected: bool,
+) -> u32 {
+    let count_flag = if count_corrected {
+        sanitization::CorrectedTexelCounter::FLAG
+    } else {
+        0
+    };
+
+    quality.flags() | sanitization.flags() | count_flag
+}
+
+/// A trade-off between compression speed and quality.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Quality {
+    /// Only use the single-region mode, skipping the evaluation of the 32 two-region
+    /// partitions.
+    Fast,
+    /// Use both single-region and two-region modes.
+    #[default]
+    Normal,
+    /// Fully encode every two-region partition as well as the single-region mode, and keep the
+    /// one with the lowest error, rather than only encoding the partition that looks best. The
+    /// endpoints of each partition are optimized too, and refined twice, as with
+    /// `optimize_endpoints` and `refinement_passes` of `Refined`. Much slower, for offline bakes.
+    ///
+    /// A workgroup cooperates on each block. Only the WGSL shaders have the cooperative encoder,
+    /// `encode_block_cooperatively`, so they are used even on SPIR-V builds.
+    ///
+    /// Not supported by [`BatchCompressor`](batch::BatchCompressor) or
+    /// `compress_to_buffer_indirect`, which dispatch one thread to a block. They panic in debug
+    /// builds when passed it.
+    High,
+    /// Like `Normal`, but fully encode the `partitions` two-region partitions that look best,
+    /// rather than only the first, and keep the one with the lowest error. `partitions` is
+    /// clamped to 1..=32, so 1 partition with no other options matches `Normal`, and 32
+    /// partitions with the options of `High` match it, one thread to a block.
+    Refined {
+        partitions: u32,
+        /// Also encode each partition with its endpoints inset and fitted by least squares to
+        /// its indices, as the single-region mode always is, and keep whichever has the lower
+        /// error. Roughly doubles the cost of each partition.
+        optimize_endpoints: bool,
+        /// How many times to refit the endpoints by least squares to indices picked by their
+        /// actual error against the quantized endpoints, rather than by projecting onto the line
+        /// between them, keeping whichever encoding has the lowest error. The single-region
+        /// endpoints are also rounded up or down, channel by channel, to whichever is closer.
+        /// Clamped to 0..=7, where 0 turns refinement off. Each pass costs several times as
+        /// much as encoding without it.
+        refinement_passes: u32,
+    },
+}
+
+impl Quality {
+    // Matches the `FLAG_` constants in the shader.
+    fn flags(self) -> u32 {
+        const FLAG_ENCODE_P2: u32 = 1;
+        const FLAG_OPTIMIZE_ENDPOINTS_P2: u32 = 16;
+        const FLAGS_PARTITIONS_SHIFT: u32 = 8;
+        const FLAGS_REFINEMENT_PASSES_SHIFT: u32 = 16;
+
+        match self {
+            Self::Fast => 0,
+            Self::Normal => FLAG_ENCODE_P2,
+            Self::High => {
+                FLAG_ENCODE_P2 | FLAG_OPTIMIZE_ENDPOINTS_P2 | (2 << FLAGS_REFINEMENT_PASSES_SHIFT)
+            }
+            Self::Refined {
+                partitions,
+                optimize_endpoints,
+                refinement_passes,
+            } => {
+                let optimize_flag = if optimize_endpoints {
+                    FLAG_OPTIMIZE_ENDPOINTS_P2
+                } else {
+                    0
+                };
+
+                FLAG_ENCODE_P2
+                    | optimize_flag
+                    | ((partitions.clamp(1, 32) - 1) << FLAGS_PARTITIONS_SHIFT)
+                    | (refinement_passes.min(7) << FLAGS_REFINEMENT_PASSES_SHIFT)
+            }
+        }
+    }
 }
 
 pub struct TextureParams<'a> {
@@ -401,6 +1070,177 @@ pub struct TextureParams<'a> {
     pub usage: wgpu::TextureUsages,
 }
 
+/// Creates a pipeline of a compressor from the `entry_point` of its WGSL `source`, such as
+/// `main_cooperative` for `Quality::High`. It takes the same bind group as the compressor's own
+/// pipeline.
+fn create_wgsl_pipeline(
+    device: &wgpu::Device,
+    label: &'static str,
+    source: &str,
+    entry_point: &str,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    push_constants: bool,
+) -> wgpu::ComputePipeline {
+    let shader = device.create_shader_module(&wgsl::shader_module_descriptor(
+        label,
+        source,
+        push_constants,
+    ));
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: if push_constants {
+            &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::COMPUTE,
+                range: 0..requirements::PUSH_CONSTANT_SIZE,
+            }]
+        } else {
+            &[]
+        },
+    });
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module: &shader,
+        entry_point,
+    })
+}
+
+/// Copies blocks laid out as by `compress_to_buffer` into a BC6H texture.
+fn copy_blocks_to_texture(
+    command_encoder: &mut wgpu::CommandEncoder,
+    buffer: &wgpu::Buffer,
+    extent: wgpu::Extent3d,
+    destination: wgpu::ImageCopyTexture,
+) {
+    command_encoder.copy_buffer_to_texture(
+        wgpu::ImageCopyBuffer {
+            buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                // width / 4 (because a block contains 4 pixels horizontally) * 16 (the block size)
+                // confusing, I know.
+                bytes_per_row: std::num::NonZeroU32::new(extent.width * 4),
+                // Counted in rows of blocks, not texels.
+                rows_per_image: std::num::NonZeroU32::new(extent.height / 4),
+            },
+        },
+        destination,
+        extent,
+    );
+}
+
+fn placeholder_counter(device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("wgpu-bc6h-compression placeholder corrected texel count"),
+        size: 4,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    })
+}
+
+/// The blocks compressed by a single dispatch: `rows` of each slice in `slices`. Tiles that span
+/// several slices cover them entirely.
+struct Tile {
+    rows: std::ops::Range<u32>,
+    slices: std::ops::Range<u32>,
+}
+
+/// Where a tile's blocks go in the output buffer.
+struct TileBinding {
+    offset: u64,
+    size: u64,
+    /// The index of the tile's first block from `offset`, which is rounded down to the storage
+    /// buffer offset alignment.
+    index_offset: u32,
+}
+
+impl Tile {
+    fn binding(
+        &self,
+        device: &wgpu::Device,
+        width_in_blocks: u32,
+        height_in_blocks: u32,
+    ) -> TileBinding {
+        let alignment = device.limits().min_storage_buffer_offset_alignment as u64;
+
+        let first_block = (self.slices.start as u64 * height_in_blocks as u64
+            + self.rows.start as u64)
+            * width_in_blocks as u64;
+        let block_count = (self.slices.end - self.slices.start) as u64
+            * (self.rows.end - self.rows.start) as u64
+            * width_in_blocks as u64;
+
+        let start = first_block * 16;
+        let offset = start / alignment * alignment;
+
+        TileBinding {
+            offset,
+            size: start + block_count * 16 - offset,
+            index_offset: ((start - offset) / 16) as u32,
+        }
+    }
+}
+
+/// Splits rows of blocks, counted across all the slices, into tiles that fit in a single dispatch
+/// and storage buffer binding.
+fn tiles(
+    device: &wgpu::Device,
+    width_in_blocks: u32,
+    height_in_blocks: u32,
+    rows: std::ops::Range<u32>,
+    group_size: [u32; 2],
+) -> Vec<Tile> {
+    let limits = device.limits();
+    let alignment = limits.min_storage_buffer_offset_alignment as u64;
+    let max_workgroups = limits.max_compute_workgroups_per_dimension as u64;
+
+    // Leaves room for the binding offset being rounded down.
+    let max_blocks = (limits.max_storage_buffer_binding_size as u64 - (alignment - 16)) / 16;
+    let row_blocks = (width_in_blocks as u64).max(1);
+    let slice_blocks = row_blocks * height_in_blocks as u64;
+
+    let max_rows = (max_blocks / row_blocks)
+        .min(max_workgroups * group_size[0] as u64)
+        .max(1) as u32;
+    let max_slices = (max_blocks / slice_blocks.max(1))
+        .min(max_workgroups * group_size[1] as u64)
+        .max(1) as u32;
+
+    let mut tiles = Vec::new();
+    let mut row = rows.start;
+
+    while row < rows.end {
+        let slice = row / height_in_blocks;
+        let row_in_slice = row % height_in_blocks;
+
+        if row_in_slice == 0 && rows.end - row >= height_in_blocks && height_in_blocks <= max_rows {
+            let slice_count = ((rows.end - row) / height_in_blocks).min(max_slices);
+
+            tiles.push(Tile {
+                rows: 0..height_in_blocks,
+                slices: slice..slice + slice_count,
+            });
+            row += slice_count * height_in_blocks;
+        } else {
+            let end = rows
+                .end
+                .min((slice + 1) * height_in_blocks)
+                .min(row + max_rows);
+
+            tiles.push(Tile {
+                rows: row_in_slice..end - slice * height_in_blocks,
+                slices: slice..slice + 1,
+            });
+            row = end;
+        }
+    }
+
+    tiles
+}
+
 fn dispatch_count(num: u32, group_size: u32) -> u32 {
     let mut count = num / group_size;
     let rem = num % group_size;
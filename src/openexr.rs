@@ -0,0 +1,152 @@
+//! Reading [OpenEXR](https://www.openexr.com/) images, behind the `exr` feature.
+//!
+//! Half and float channels, scanline and tiled images, and all of the compression methods
+//! supported by the [`exr`] crate (including ZIP and PIZ) can be read.
+
+use crate::HdrImage;
+use exr::prelude::{AnyChannel, FlatSamples, ReadChannels, ReadLayers};
+use std::io::{Read, Seek};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ExrError {
+    Exr(exr::error::Error),
+    /// No layer (or channel prefix) with the requested name exists in the file.
+    LayerNotFound(String),
+    /// The selected layer doesn't have the named colour channel.
+    MissingChannel(String),
+}
+
+impl std::fmt::Display for ExrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Exr(error) => write!(f, "{}", error),
+            Self::LayerNotFound(layer) => write!(f, "No layer named '{}' in the image", layer),
+            Self::MissingChannel(channel) => write!(f, "Missing the '{}' channel", channel),
+        }
+    }
+}
+
+impl std::error::Error for ExrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Exr(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<exr::error::Error> for ExrError {
+    fn from(error: exr::error::Error) -> Self {
+        Self::Exr(error)
+    }
+}
+
+/// Read the RGB(A) channels of an EXR file. See [`read_exr_from_reader`] for how `layer` is used.
+pub fn read_exr(path: impl AsRef<Path>, layer: Option<&str>) -> Result<HdrImage, ExrError> {
+    let image = exr::prelude::read()
+        .no_deep_data()
+        .largest_resolution_level()
+        .all_channels()
+        .all_layers()
+        .all_attributes()
+        .from_file(path)?;
+
+    select_layer(&image.layer_data, layer)
+}
+
+/// Read the RGB(A) channels of an EXR image.
+///
+/// With a `layer` of `None`, the `R`, `G`, `B` and `A` channels of the first layer are used.
+/// Otherwise, either a layer (part) with that name, or channels prefixed with that name
+/// (such as `diffuse.R`, `diffuse.G` and `diffuse.B` for a layer of `diffuse`) are used.
+///
+/// A missing alpha channel is filled with `1.0`.
+pub fn read_exr_from_reader(
+    reader: impl Read + Seek,
+    layer: Option<&str>,
+) -> Result<HdrImage, ExrError> {
+    let image = exr::prelude::read()
+        .no_deep_data()
+        .largest_resolution_level()
+        .all_channels()
+        .all_layers()
+        .all_attributes()
+        .from_buffered(std::io::BufReader::new(reader))?;
+
+    select_layer(&image.layer_data, layer)
+}
+
+type FlatLayer = exr::prelude::Layer<exr::prelude::AnyChannels<FlatSamples>>;
+
+fn select_layer(layers: &[FlatLayer], name: Option<&str>) -> Result<HdrImage, ExrError> {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            let layer = layers
+                .first()
+                .ok_or_else(|| ExrError::LayerNotFound(String::new()))?;
+            return layer_to_image(layer, "");
+        }
+    };
+
+    if let Some(layer) = layers.iter().find(|layer| {
+        layer
+            .attributes
+            .layer_name
+            .as_ref()
+            .is_some_and(|layer_name| layer_name.eq(name))
+    }) {
+        return layer_to_image(layer, "");
+    }
+
+    let prefix = format!("{}.", name);
+
+    let layer = layers
+        .iter()
+        .find(|layer| find_channel(layer, &prefix, "R").is_some())
+        .ok_or_else(|| ExrError::LayerNotFound(name.to_string()))?;
+
+    layer_to_image(layer, &prefix)
+}
+
+fn find_channel<'a>(
+    layer: &'a FlatLayer,
+    prefix: &str,
+    channel: &str,
+) -> Option<&'a AnyChannel<FlatSamples>> {
+    layer.channel_data.list.iter().find(|candidate| {
+        let name = candidate.name.to_string();
+        name.strip_prefix(prefix) == Some(channel)
+    })
+}
+
+fn layer_to_image(layer: &FlatLayer, prefix: &str) -> Result<HdrImage, ExrError> {
+    let channel = |name: &str| {
+        find_channel(layer, prefix, name)
+            .ok_or_else(|| ExrError::MissingChannel(format!("{}{}", prefix, name)))
+    };
+
+    let red = channel("R")?;
+    let green = channel("G")?;
+    let blue = channel("B")?;
+    let alpha = channel("A").ok();
+
+    let width = layer.size.width();
+    let height = layer.size.height();
+
+    let pixels = (0..width * height)
+        .map(|i| {
+            [
+                red.sample_data.value_by_flat_index(i).to_f32(),
+                green.sample_data.value_by_flat_index(i).to_f32(),
+                blue.sample_data.value_by_flat_index(i).to_f32(),
+                alpha.map_or(1.0, |alpha| {
+                    alpha.sample_data.value_by_flat_index(i).to_f32()
+                }),
+            ]
+        })
+        .collect();
+
+    Ok(HdrImage::new(width as u32, height as u32, 1, pixels))
+}
@@ -0,0 +1,104 @@
+//! Reading and writing [Portable Float Map](http://www.pauldebevec.com/Research/HDR/PFM/) images.
+
+use crate::HdrImage;
+use std::io::{self, BufRead, Write};
+
+/// Read a colour (`PF`) or greyscale (`Pf`) PFM image. Greyscale images are expanded to RGB,
+/// and the alpha channel is set to `1.0`.
+pub fn read_pfm(mut reader: impl BufRead) -> io::Result<HdrImage> {
+    let channels = match read_token(&mut reader)?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        other => return Err(invalid_data(format!("Unknown PFM type '{}'", other))),
+    };
+
+    let width: u32 = parse_token(&mut reader)?;
+    let height: u32 = parse_token(&mut reader)?;
+    let scale: f32 = parse_token(&mut reader)?;
+    let little_endian = scale < 0.0;
+
+    let mut bytes = vec![0; width as usize * height as usize * channels * 4];
+    reader.read_exact(&mut bytes)?;
+
+    let floats: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|bytes| {
+            let bytes = bytes.try_into().unwrap();
+            if little_endian {
+                f32::from_le_bytes(bytes)
+            } else {
+                f32::from_be_bytes(bytes)
+            }
+        })
+        .collect();
+
+    // Rows are stored from the bottom of the image to the top. An image with no columns has no
+    // floats to split, but `chunks_exact` panics on a chunk size of 0.
+    let pixels = floats
+        .chunks_exact((width as usize * channels).max(1))
+        .rev()
+        .flat_map(|row| row.chunks_exact(channels))
+        .map(|texel| match *texel {
+            [r, g, b] => [r, g, b, 1.0],
+            [luminance] => [luminance, luminance, luminance, 1.0],
+            _ => unreachable!(),
+        })
+        .collect();
+
+    Ok(HdrImage::new(width, height, 1, pixels))
+}
+
+/// Write the RGB channels of a 2D image as a little-endian colour PFM.
+pub fn write_pfm(mut writer: impl Write, image: &HdrImage) -> io::Result<()> {
+    if image.is_3d() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "PFM images can't have a depth",
+        ));
+    }
+
+    write!(writer, "PF\n{} {}\n-1.0\n", image.width, image.height)?;
+
+    for row in image.pixels.chunks_exact(image.width.max(1) as usize).rev() {
+        for [r, g, b, _] in row {
+            writer.write_all(&r.to_le_bytes())?;
+            writer.write_all(&g.to_le_bytes())?;
+            writer.write_all(&b.to_le_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_token(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut token = Vec::new();
+
+    loop {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+
+        if byte[0].is_ascii_whitespace() {
+            // Skip leading whitespace. Only the single whitespace character after the last token
+            // may be consumed, as the pixel data follows it directly.
+            if token.is_empty() {
+                continue;
+            }
+            break;
+        }
+
+        token.push(byte[0]);
+    }
+
+    String::from_utf8(token).map_err(|_| invalid_data("PFM header is not valid ASCII".into()))
+}
+
+fn parse_token<T: std::str::FromStr>(reader: &mut impl BufRead) -> io::Result<T> {
+    let token = read_token(reader)?;
+    token
+        .parse()
+        .map_err(|_| invalid_data(format!("Invalid PFM header value '{}'", token)))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,153 @@
+//! Measuring how long compression takes on the GPU with timestamp queries.
+
+use std::cell::RefCell;
+use std::time::Duration;
+
+/// Records a timestamp before and after each compression pass that it is passed to through
+/// [`CompressionParams::profiler`](crate::CompressionParams::profiler).
+///
+/// Once the passes have been recorded, call [`resolve`](Self::resolve) on the same or a later
+/// command encoder, submit it, then [`read`](Self::read) the timings. [`reset`](Self::reset)
+/// makes room for more passes.
+pub struct Profiler {
+    query_set: wgpu::QuerySet,
+    buffer: wgpu::Buffer,
+    capacity: u32,
+    timestamp_period: f32,
+    // The number of blocks compressed by each recorded pass.
+    passes: RefCell<Vec<u64>>,
+}
+
+impl Profiler {
+    /// Returns `None` if the device doesn't have [`wgpu::Features::TIMESTAMP_QUERY`]. Passes past
+    /// the first `max_passes` aren't recorded.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, max_passes: u32) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let capacity = max_passes
+            .saturating_mul(2)
+            .min(wgpu::QUERY_SET_MAX_QUERIES);
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("wgpu-bc6h-compression timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: capacity,
+        });
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("wgpu-bc6h-compression timestamps"),
+            size: capacity as u64 * 8,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            buffer,
+            capacity,
+            timestamp_period: queue.get_timestamp_period(),
+            passes: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Writes the timestamp before a pass, returning its index if there's room for it.
+    pub(crate) fn begin_pass(
+        &self,
+        command_encoder: &mut wgpu::CommandEncoder,
+        blocks: u64,
+    ) -> Option<u32> {
+        let mut passes = self.passes.borrow_mut();
+        let index = passes.len() as u32;
+
+        if (index + 1) * 2 > self.capacity {
+            return None;
+        }
+
+        command_encoder.write_timestamp(&self.query_set, index * 2);
+        passes.push(blocks);
+
+        Some(index)
+    }
+
+    pub(crate) fn end_pass(&self, command_encoder: &mut wgpu::CommandEncoder, index: u32) {
+        command_encoder.write_timestamp(&self.query_set, index * 2 + 1);
+    }
+
+    /// Copies the timestamps of the recorded passes into a buffer that `read` can map.
+    pub fn resolve(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        let count = self.passes.borrow().len() as u32 * 2;
+
+        if count > 0 {
+            command_encoder.resolve_query_set(&self.query_set, 0..count, &self.buffer, 0);
+        }
+    }
+
+    /// Reads back the timings of the recorded passes, in the order they were recorded. The
+    /// commands from `resolve` need to have been submitted first.
+    pub async fn read(
+        &self,
+        device: &wgpu::Device,
+    ) -> Result<Vec<PassTiming>, wgpu::BufferAsyncError> {
+        let passes = self.passes.borrow().clone();
+
+        if passes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let slice = self.buffer.slice(..passes.len() as u64 * 16);
+        let map_future = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        map_future.await?;
+
+        let timings = {
+            let bytes = slice.get_mapped_range();
+
+            passes
+                .iter()
+                .zip(bytes.chunks_exact(16))
+                .map(|(&blocks, timestamps)| {
+                    let start = u64::from_le_bytes(timestamps[..8].try_into().unwrap());
+                    let end = u64::from_le_bytes(timestamps[8..].try_into().unwrap());
+                    let nanoseconds =
+                        end.saturating_sub(start) as f64 * self.timestamp_period as f64;
+
+                    PassTiming {
+                        duration: Duration::from_nanos(nanoseconds as u64),
+                        blocks,
+                    }
+                })
+                .collect()
+        };
+
+        self.buffer.unmap();
+
+        Ok(timings)
+    }
+
+    /// Forgets the recorded passes.
+    pub fn reset(&self) {
+        self.passes.borrow_mut().clear();
+    }
+}
+
+/// How long a compression pass took on the GPU.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PassTiming {
+    pub duration: Duration,
+    /// The number of 4x4 blocks that the pass compressed.
+    pub blocks: u64,
+}
+
+impl PassTiming {
+    /// `None` if the pass took no measurable time, which can happen for small passes on devices
+    /// with a coarse timestamp period.
+    pub fn blocks_per_second(&self) -> Option<f64> {
+        if self.duration.is_zero() {
+            None
+        } else {
+            Some(self.blocks as f64 / self.duration.as_secs_f64())
+        }
+    }
+}
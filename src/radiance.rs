@@ -0,0 +1,134 @@
+//! Reading [Radiance HDR](https://en.wikipedia.org/wiki/RGBE_image_format) (`.hdr`) images.
+
+use crate::HdrImage;
+use std::io::{self, BufRead};
+
+/// Read an RGBE image, with either run-length encoded or flat scanlines. The alpha channel is set
+/// to `1.0`.
+pub fn read_hdr(mut reader: impl BufRead) -> io::Result<HdrImage> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    if !line.starts_with("#?") {
+        return Err(invalid_data("Missing the Radiance HDR signature".into()));
+    }
+
+    // Header variables, terminated by an empty line.
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid_data("Unexpected end of the header".into()));
+        }
+
+        let line = line.trim();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(invalid_data(format!("Unsupported format '{}'", format)));
+            }
+        }
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+
+    let (height, width) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => (height.parse::<u32>().ok(), width.parse::<u32>().ok()),
+        _ => (None, None),
+    };
+
+    let (width, height) = match (width, height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => {
+            return Err(invalid_data(format!(
+                "Unsupported resolution line '{}'",
+                line.trim()
+            )))
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    let mut scanline = vec![[0_u8; 4]; width as usize];
+
+    for _ in 0..height {
+        read_scanline(&mut reader, &mut scanline)?;
+        pixels.extend(scanline.iter().map(|&rgbe| rgbe_to_rgba(rgbe)));
+    }
+
+    Ok(HdrImage::new(width, height, 1, pixels))
+}
+
+fn read_scanline(reader: &mut impl BufRead, scanline: &mut [[u8; 4]]) -> io::Result<()> {
+    let mut start = [0; 4];
+    reader.read_exact(&mut start)?;
+
+    let width = scanline.len();
+    let run_length_encoded = (8..0x8000).contains(&width) && start[0] == 2 && start[1] == 2;
+
+    if !run_length_encoded {
+        scanline[0] = start;
+        for rgbe in &mut scanline[1..] {
+            reader.read_exact(rgbe)?;
+        }
+        return Ok(());
+    }
+
+    if ((start[2] as usize) << 8 | start[3] as usize) != width {
+        return Err(invalid_data("Scanline width mismatch".into()));
+    }
+
+    // Each channel is run-length encoded separately.
+    for channel in 0..4 {
+        let mut x = 0;
+
+        while x < width {
+            let mut count = [0];
+            reader.read_exact(&mut count)?;
+
+            let (count, run) = if count[0] > 128 {
+                (count[0] as usize - 128, true)
+            } else {
+                (count[0] as usize, false)
+            };
+
+            if count == 0 || x + count > width {
+                return Err(invalid_data("Invalid scanline run length".into()));
+            }
+
+            if run {
+                let mut value = [0];
+                reader.read_exact(&mut value)?;
+                for rgbe in &mut scanline[x..x + count] {
+                    rgbe[channel] = value[0];
+                }
+            } else {
+                for rgbe in &mut scanline[x..x + count] {
+                    let mut value = [0];
+                    reader.read_exact(&mut value)?;
+                    rgbe[channel] = value[0];
+                }
+            }
+
+            x += count;
+        }
+    }
+
+    Ok(())
+}
+
+fn rgbe_to_rgba([r, g, b, e]: [u8; 4]) -> [f32; 4] {
+    if e == 0 {
+        return [0.0, 0.0, 0.0, 1.0];
+    }
+
+    let scale = 2.0_f32.powi(e as i32 - (128 + 8));
+    [r as f32 * scale, g as f32 * scale, b as f32 * scale, 1.0]
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,214 @@
+//! The [`wgpu::Features`] and [`wgpu::Limits`] that a device needs for the compressors.
+
+/// How the compressors are going to be used, which decides what they need.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Configuration {
+    /// Whether BC6H textures are going to be created, with `compress_to_texture` or an
+    /// [`IncrementalCompressor`](crate::incremental::IncrementalCompressor). Compressing to a
+    /// buffer needs no texture compression feature.
+    pub compressed_textures: bool,
+    /// Whether to pass the constants to the compressors in push constants rather than uniform
+    /// buffers. The compressors use them whenever the device allows, so this decides whether the
+    /// device asks for them.
+    pub push_constants: bool,
+}
+
+impl Configuration {
+    /// Uses push constants if the adapter has them.
+    pub fn for_adapter(adapter: &wgpu::Adapter) -> Self {
+        Self {
+            compressed_textures: false,
+            push_constants: adapter.features().contains(wgpu::Features::PUSH_CONSTANTS)
+                && adapter.limits().max_push_constant_size >= PUSH_CONSTANT_SIZE,
+        }
+    }
+}
+
+// The size of the constants of both compressors, a `[u32; 8]`.
+pub(crate) const PUSH_CONSTANT_SIZE: u32 = 32;
+
+/// Whether the compressors can take their constants as push constants on `device`.
+pub(crate) fn can_use_push_constants(device: &wgpu::Device) -> bool {
+    device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+        && device.limits().max_push_constant_size >= PUSH_CONSTANT_SIZE
+}
+
+/// The features and limits to create a device with. Pass them to
+/// [`wgpu::Adapter::request_device`], or raise the limits that the rest of the application needs
+/// with [`raise_limits`](Self::raise_limits).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceRequirements {
+    pub features: wgpu::Features,
+    /// The downlevel defaults, with the limits the compressors depend on raised to what they
+    /// need. Use [`wgpu::Limits::using_resolution`] to allow larger textures.
+    pub limits: wgpu::Limits,
+}
+
+impl DeviceRequirements {
+    pub fn new(configuration: &Configuration) -> Self {
+        let mut features = wgpu::Features::empty();
+
+        if configuration.push_constants {
+            features |= wgpu::Features::PUSH_CONSTANTS;
+        }
+
+        if configuration.compressed_textures {
+            features |= wgpu::Features::TEXTURE_COMPRESSION_BC;
+        }
+
+        let mut requirements = Self {
+            features,
+            limits: wgpu::Limits::downlevel_defaults(),
+        };
+
+        requirements.limits = requirements.raise_limits(requirements.limits.clone());
+        requirements
+    }
+
+    /// Raises the limits that the compressors depend on to what they need, leaving the others as
+    /// they are.
+    pub fn raise_limits(&self, mut limits: wgpu::Limits) -> wgpu::Limits {
+        for (_, limit, minimum) in minimum_limits(self.push_constants()) {
+            let limit = limit(&mut limits);
+            *limit = (*limit).max(minimum);
+        }
+
+        limits
+    }
+
+    /// Checks that `device` was created with the features and limits the compressors need.
+    pub fn check(&self, device: &wgpu::Device) -> Result<(), RequirementsError> {
+        let missing_features = self.features - device.features();
+
+        if !missing_features.is_empty() {
+            return Err(RequirementsError::MissingFeatures(missing_features));
+        }
+
+        let mut device_limits = device.limits();
+
+        for (name, limit, minimum) in minimum_limits(self.push_constants()) {
+            let available = *limit(&mut device_limits);
+
+            if available < minimum {
+                return Err(RequirementsError::LimitTooLow {
+                    name,
+                    required: minimum,
+                    available,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push_constants(&self) -> bool {
+        self.features.contains(wgpu::Features::PUSH_CONSTANTS)
+    }
+}
+
+type LimitField = fn(&mut wgpu::Limits) -> &mut u32;
+
+// The limits that the compressors depend on, with their names and the lowest values they work
+// with. Their other bindings are sized and split up to fit the device's limits.
+fn minimum_limits(push_constants: bool) -> [(&'static str, LimitField, u32); 12] {
+    let push_constant_size = if push_constants {
+        PUSH_CONSTANT_SIZE
+    } else {
+        0
+    };
+
+    [
+        ("max_bind_groups", |limits| &mut limits.max_bind_groups, 1),
+        (
+            "max_sampled_textures_per_shader_stage",
+            |limits| &mut limits.max_sampled_textures_per_shader_stage,
+            1,
+        ),
+        (
+            "max_samplers_per_shader_stage",
+            |limits| &mut limits.max_samplers_per_shader_stage,
+            1,
+        ),
+        // The blocks, the corrected texel counter and the job table of a batch.
+        (
+            "max_storage_buffers_per_shader_stage",
+            |limits| &mut limits.max_storage_buffers_per_shader_stage,
+            3,
+        ),
+        (
+            "max_uniform_buffers_per_shader_stage",
+            |limits| &mut limits.max_uniform_buffers_per_shader_stage,
+            if push_constant_size > 0 { 0 } else { 1 },
+        ),
+        (
+            "max_push_constant_size",
+            |limits| &mut limits.max_push_constant_size,
+            push_constant_size,
+        ),
+        // 8x8x1 for 2D textures, 4x4x4 for 3D ones and 32x1x1 for `Quality::High`, which has a
+        // thread for each two-region partition.
+        (
+            "max_compute_invocations_per_workgroup",
+            |limits| &mut limits.max_compute_invocations_per_workgroup,
+            64,
+        ),
+        (
+            "max_compute_workgroup_size_x",
+            |limits| &mut limits.max_compute_workgroup_size_x,
+            32,
+        ),
+        (
+            "max_compute_workgroup_size_y",
+            |limits| &mut limits.max_compute_workgroup_size_y,
+            8,
+        ),
+        (
+            "max_compute_workgroup_size_z",
+            |limits| &mut limits.max_compute_workgroup_size_z,
+            4,
+        ),
+        // The texels of the block and the error and encoding of each partition that a
+        // `Quality::High` workgroup shares: 16 `vec3<f32>`s, 32 `f32`s and 32 `vec4<u32>`s.
+        (
+            "max_compute_workgroup_storage_size",
+            |limits| &mut limits.max_compute_workgroup_storage_size,
+            16 * 16 + 32 * 4 + 32 * 16,
+        ),
+        (
+            "max_compute_workgroups_per_dimension",
+            |limits| &mut limits.max_compute_workgroups_per_dimension,
+            1,
+        ),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequirementsError {
+    MissingFeatures(wgpu::Features),
+    LimitTooLow {
+        name: &'static str,
+        required: u32,
+        available: u32,
+    },
+}
+
+impl std::fmt::Display for RequirementsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::MissingFeatures(features) => {
+                write!(f, "The device is missing the {:?} features", features)
+            }
+            Self::LimitTooLow {
+                name,
+                required,
+                available,
+            } => write!(
+                f,
+                "The device's {} is {}, but at least {} is needed",
+                name, available, required
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RequirementsError {}
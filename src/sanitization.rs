@@ -0,0 +1,95 @@
+//! Making texels that BC6H can't represent safe to compress.
+
+/// What the compressors do with texels that an unsigned half can't represent. A single NaN or
+/// negative value otherwise throws off the endpoints of its whole block, and values past 65504
+/// overflow to infinity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Sanitization {
+    /// Trust the input to be finite and non-negative.
+    None,
+    /// Clamp negative values to zero and larger ones, including infinities, to 65504. Replace
+    /// NaNs with zero.
+    #[default]
+    ReplaceNanWithZero,
+    /// Clamp like [`ReplaceNanWithZero`](Self::ReplaceNanWithZero), but replace each NaN with the
+    /// average of the same channel over the rest of its 4x4 block, or zero if there is none.
+    ReplaceNanWithNeighbourAverage,
+}
+
+impl Sanitization {
+    // Matches the `FLAG_` constants in the shader.
+    pub(crate) fn flags(self) -> u32 {
+        const FLAG_SANITIZE: u32 = 2;
+        const FLAG_NAN_TO_AVERAGE: u32 = 4;
+
+        match self {
+            Self::None => 0,
+            Self::ReplaceNanWithZero => FLAG_SANITIZE,
+            Self::ReplaceNanWithNeighbourAverage => FLAG_SANITIZE | FLAG_NAN_TO_AVERAGE,
+        }
+    }
+}
+
+/// Counts the texels that were changed by the [`Sanitization`] of the compressions that it is
+/// passed to through
+/// [`CompressionParams::corrected_texel_counter`](crate::CompressionParams::corrected_texel_counter).
+///
+/// The count accumulates until [`clear`](Self::clear) is called. To read it, call
+/// [`resolve`](Self::resolve) after the compressions, submit the commands, then
+/// [`read`](Self::read).
+///
+/// Compressions that are passed a counter use the WGSL shaders, even where the compressors
+/// otherwise use the SPIR-V generated from them, as wgpu can't parse the atomics it needs from
+/// SPIR-V.
+pub struct CorrectedTexelCounter {
+    pub(crate) buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+}
+
+impl CorrectedTexelCounter {
+    // Matches `FLAG_COUNT_CORRECTED` in `bc6h.wgsl`.
+    pub(crate) const FLAG: u32 = 8;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("wgpu-bc6h-compression corrected texel count"),
+                size: 4,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("wgpu-bc6h-compression corrected texel count readback"),
+                size: 4,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        }
+    }
+
+    /// Sets the count back to zero.
+    pub fn clear(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.clear_buffer(&self.buffer, 0, None);
+    }
+
+    /// Copies the count into a buffer that `read` can map.
+    pub fn resolve(&self, command_encoder: &mut wgpu::CommandEncoder) {
+        command_encoder.copy_buffer_to_buffer(&self.buffer, 0, &self.readback_buffer, 0, 4);
+    }
+
+    /// Reads back the count. The commands from `resolve` need to have been submitted first.
+    pub async fn read(&self, device: &wgpu::Device) -> Result<u32, wgpu::BufferAsyncError> {
+        let slice = self.readback_buffer.slice(..);
+        let map_future = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        map_future.await?;
+
+        let count = u32::from_le_bytes(slice.get_mapped_range()[..].try_into().unwrap());
+
+        self.readback_buffer.unmap();
+
+        Ok(count)
+    }
+}
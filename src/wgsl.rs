@@ -0,0 +1,29 @@
+//! The WGSL compression shaders, for targets that don't accept SPIR-V such as browsers, and for the
+//! batch compressor, corrected texel counting and `Quality::High` everywhere. The SPIR-V in
+//! `shaders/compiled` is generated from them, see `shaders/spirv.rs`.
+
+const ENCODER: &str = include_str!("../shaders/bc6h.wgsl");
+const COUNT_CORRECTED: &str = include_str!("../shaders/count_corrected.wgsl");
+
+const UNIFORM_CONSTANTS: &str = "[[group(0), binding(3)]]\nvar<uniform> constants: Constants;";
+const PUSH_CONSTANTS: &str = "var<push_constant> constants: Constants;";
+
+/// Prepends the shared block encoder and the corrected texel counter to the 2D, 3D or batch entry
+/// point. WGSL has no preprocessor, so the uniform buffer binding is swapped for a push constant
+/// block here instead.
+pub(crate) fn shader_module_descriptor(
+    label: &'static str,
+    entry_point: &str,
+    push_constants: bool,
+) -> wgpu::ShaderModuleDescriptor<'static> {
+    let mut source = format!("{}\n{}\n{}", ENCODER, COUNT_CORRECTED, entry_point);
+
+    if push_constants {
+        source = source.replace(UNIFORM_CONSTANTS, PUSH_CONSTANTS);
+    }
+
+    wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    }
+}
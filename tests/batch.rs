@@ -0,0 +1,138 @@
+//! Checks that a batch writes the same blocks as compressing each job on its own, in one dispatch
+//! or split over several. Skipped without an adapter unless `BC6H_REQUIRE_ADAPTER` is set.
+
+mod common;
+
+use common::{ErrorMetrics, Scene};
+use wgpu_bc6h_compression::{
+    batch::{BatchCompressor, BatchJob, BatchParams},
+    decode,
+    sanitization::Sanitization,
+    Quality,
+};
+
+#[test]
+fn jobs_match_separate_compressions() {
+    if let Some(gpu) = common::gpu_for_test() {
+        check(&gpu);
+    }
+}
+
+#[test]
+fn jobs_split_to_fit_storage_buffer_bindings_match_separate_compressions() {
+    // The 1 KiB of blocks span more than a binding of 768 bytes, so the last job, and the bytes
+    // before it up to the offset alignment, go into a dispatch of their own.
+    let gpu = common::gpu_for_test_with_limits(|limits| wgpu::Limits {
+        max_storage_buffer_binding_size: 768,
+        ..limits
+    });
+
+    if let Some(gpu) = gpu {
+        check(&gpu);
+    }
+}
+
+fn check(gpu: &common::Gpu) {
+    // Different sizes, including one wider than a workgroup of blocks and one cut out of a
+    // larger image.
+    let images = [
+        Scene::Gradient.image(16, 16, 1),
+        Scene::Noise.image(40, 8, 1),
+        Scene::SunDisk.image(4, 4, 1),
+        Scene::ConstantBlocks.image(64, 64, 1),
+    ];
+    let sources: Vec<_> = images
+        .iter()
+        .map(|image| image.create_texture(&gpu.device, &gpu.queue, None))
+        .collect();
+
+    let cropped_origin = [8, 20];
+    let cropped_extent = wgpu::Extent3d {
+        width: 36,
+        height: 12,
+        depth_or_array_layers: 1,
+    };
+
+    let mut expected = Vec::new();
+    let mut jobs = Vec::new();
+
+    for (i, (image, source)) in images.iter().zip(&sources).enumerate() {
+        let (image, origin) = if i == 3 {
+            (
+                common::crop(
+                    image,
+                    [cropped_origin[0], cropped_origin[1], 0],
+                    cropped_extent,
+                ),
+                cropped_origin,
+            )
+        } else {
+            (image.clone(), [0, 0])
+        };
+
+        jobs.push(BatchJob {
+            source: wgpu::ImageCopyTexture {
+                texture: source,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin[0],
+                    y: origin[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            extent: image.extent(),
+            output_offset: expected.len() as u64,
+        });
+
+        expected.extend(gpu.compress_image(&image, Quality::Normal));
+    }
+
+    let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: expected.len() as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let mut command_encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    BatchCompressor::new(&gpu.device).compress_to_buffer(
+        &gpu.device,
+        &mut command_encoder,
+        &BatchParams {
+            bind_group_label: None,
+            quality: Quality::Normal,
+            profiler: None,
+            sanitization: Sanitization::default(),
+            corrected_texel_counter: None,
+        },
+        &jobs,
+        &buffer,
+    );
+
+    gpu.queue.submit(Some(command_encoder.finish()));
+
+    // The WGSL port can round differently from the SPIR-V that `compress_image` may use, so
+    // compare the decoded errors rather than the bytes.
+    let batched = gpu.read(&buffer, expected.len() as u64);
+
+    for job in &jobs {
+        let range = job.output_offset as usize
+            ..job.output_offset as usize + common::compressed_size(job.extent) as usize;
+
+        let separate = decode::decode_blocks(&expected[range.clone()], job.extent, false);
+        let batched = decode::decode_blocks(&batched[range], job.extent, false);
+
+        let errors = ErrorMetrics::measure(&separate, &batched);
+
+        assert!(
+            errors.log_rmse < 0.01,
+            "job at {} differs: {:?}",
+            job.output_offset,
+            errors
+        );
+    }
+}
@@ -0,0 +1,382 @@
+//! Synthetic HDR images and a headless device, shared by the tests and the benchmarks.
+//!
+//! The device is whatever adapter wgpu picks, which can be narrowed down with `WGPU_BACKEND` and
+//! `WGPU_ADAPTER_NAME`, such as `WGPU_BACKEND=vulkan WGPU_ADAPTER_NAME=llvmpipe` to use lavapipe
+//! on machines without a GPU.
+
+#![allow(dead_code)]
+
+use wgpu_bc6h_compression::{
+    profiling::Profiler,
+    requirements::{Configuration, DeviceRequirements},
+    sanitization::Sanitization,
+    CompressionParams, Compressor2D, Compressor3D, HdrImage, Quality,
+};
+
+/// The procedurally generated images that compression is measured on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scene {
+    /// Smooth ramps over several orders of magnitude, which show banding.
+    Gradient,
+    /// Uncorrelated channels at every texel, the worst case for endpoint fitting.
+    Noise,
+    /// A dim sky with a small disk tens of thousands of times brighter, like a captured sun.
+    SunDisk,
+    /// Every block a single colour, which should be reproduced almost exactly.
+    ConstantBlocks,
+}
+
+impl Scene {
+    pub const ALL: [Self; 4] = [
+        Self::Gradient,
+        Self::Noise,
+        Self::SunDisk,
+        Self::ConstantBlocks,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gradient => "gradient",
+            Self::Noise => "noise",
+            Self::SunDisk => "sun_disk",
+            Self::ConstantBlocks => "constant_blocks",
+        }
+    }
+
+    /// The same inputs are generated on every run. Each slice of a volume is slightly different.
+    pub fn image(self, width: u32, height: u32, depth: u32) -> HdrImage {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * depth as usize);
+
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let u = (x as f32 + 0.5) / width as f32;
+                    let v = (y as f32 + 0.5) / height as f32;
+                    let w = (z as f32 + 0.5) / depth as f32;
+
+                    let [r, g, b] = match self {
+                        Self::Gradient => [
+                            (u * 16.0 - 8.0).exp2(),
+                            u * v * 4.0 + w,
+                            ((1.0 - v) * 12.0 - 6.0 + w).exp2(),
+                        ],
+                        Self::Noise => {
+                            let seed = hash(x, y, z);
+                            [0, 1, 2].map(|channel| {
+                                let random = hash(seed, channel, 0) as f32 / u32::MAX as f32;
+                                (random * 8.0 - 4.0).exp2()
+                            })
+                        }
+                        Self::SunDisk => {
+                            let radius = 1.0 / 32.0 + w / 64.0;
+                            let distance = ((u - 0.5).powi(2) + (v - 0.35).powi(2)).sqrt();
+                            let sky = [0.2 + 0.3 * v, 0.4 + 0.4 * v, 0.8 + 0.6 * v];
+
+                            if distance < radius {
+                                [60000.0, 52000.0, 40000.0]
+                            } else {
+                                let halo = 50.0 * (radius / distance).powi(4);
+                                sky.map(|sky| sky + halo)
+                            }
+                        }
+                        Self::ConstantBlocks => {
+                            let seed = hash(x / 4, y / 4, z);
+                            [0, 1, 2].map(|channel| {
+                                let random = hash(seed, channel, 1) as f32 / u32::MAX as f32;
+                                (random * 20.0 - 10.0).exp2()
+                            })
+                        }
+                    };
+
+                    pixels.push([r, g, b, 1.0]);
+                }
+            }
+        }
+
+        HdrImage::new(width, height, depth, pixels)
+    }
+}
+
+fn hash(x: u32, y: u32, z: u32) -> u32 {
+    let mut state = x
+        .wrapping_mul(0x8da6_b343)
+        .wrapping_add(y.wrapping_mul(0xd816_3841))
+        .wrapping_add(z.wrapping_mul(0xcb1a_b31f))
+        .wrapping_add(0x9e37_79b9);
+
+    state ^= state >> 16;
+    state = state.wrapping_mul(0x7feb_352d);
+    state ^= state >> 15;
+    state = state.wrapping_mul(0x846c_a68b);
+    state ^ (state >> 16)
+}
+
+/// How far a decoded image is from the original, over the RGB channels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ErrorMetrics {
+    pub rmse: f64,
+    /// The root mean squared error of `ln(1 + x)`, which is closer to what the compressors
+    /// minimise and isn't dominated by the brightest texels.
+    pub log_rmse: f64,
+    /// The mean squared `log2` error per texel, weighted by luminance, which is what the shader
+    /// minimises in each block (`CalcMSLE`).
+    pub msle: f64,
+    /// The largest error relative to the original value of the texel.
+    pub max_relative_error: f64,
+}
+
+// The weights of `CalcMSLE` in the shader.
+const LUMINANCE_WEIGHTS: [f64; 3] = [0.299, 0.587, 0.114];
+
+impl ErrorMetrics {
+    pub fn measure(original: &HdrImage, decoded: &HdrImage) -> Self {
+        assert_eq!(original.extent(), decoded.extent());
+
+        let mut squared_error = 0.0;
+        let mut squared_log_error = 0.0;
+        let mut weighted_squared_log2_error = 0.0;
+        let mut max_relative_error: f64 = 0.0;
+
+        for (original, decoded) in original.pixels.iter().zip(&decoded.pixels) {
+            for channel in 0..3 {
+                let original = original[channel] as f64;
+                let decoded = decoded[channel] as f64;
+
+                squared_error += (original - decoded).powi(2);
+                squared_log_error += (original.ln_1p() - decoded.ln_1p()).powi(2);
+                weighted_squared_log2_error += LUMINANCE_WEIGHTS[channel]
+                    * ((decoded + 1.0) / (original + 1.0)).log2().powi(2);
+                max_relative_error =
+                    max_relative_error.max((original - decoded).abs() / original.max(1e-3));
+            }
+        }
+
+        let count = original.pixels.len() as f64 * 3.0;
+
+        Self {
+            rmse: (squared_error / count).sqrt(),
+            log_rmse: (squared_log_error / count).sqrt(),
+            msle: weighted_squared_log2_error / original.pixels.len() as f64,
+            max_relative_error,
+        }
+    }
+}
+
+/// The [`Gpu`] for a test, or `None` if it should be skipped as there is no adapter. Panics
+/// instead if `BC6H_REQUIRE_ADAPTER` is set, so that CI can't quietly skip everything.
+pub fn gpu_for_test() -> Option<Gpu> {
+    gpu_for_test_with_limits(|limits| limits)
+}
+
+/// Like [`gpu_for_test`], with the device's limits adjusted by `limits`.
+pub fn gpu_for_test_with_limits(limits: impl FnOnce(wgpu::Limits) -> wgpu::Limits) -> Option<Gpu> {
+    let gpu = Gpu::with_limits(limits);
+
+    if gpu.is_none() {
+        assert!(
+            std::env::var_os("BC6H_REQUIRE_ADAPTER").is_none(),
+            "no adapter was found"
+        );
+        eprintln!("Skipping, as no adapter was found.");
+    }
+
+    gpu
+}
+
+pub struct Gpu {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub adapter_info: wgpu::AdapterInfo,
+    /// Whether BC6H textures can be created, which lavapipe doesn't allow.
+    pub compressed_textures: bool,
+    sampler: wgpu::Sampler,
+    compressor_2d: Compressor2D,
+    compressor_3d: Compressor3D,
+}
+
+impl Gpu {
+    /// Returns `None` if there is no adapter, so that callers can skip rather than fail on
+    /// machines without one. Timestamp queries and BC6H textures are enabled when the adapter has
+    /// them.
+    pub fn new() -> Option<Self> {
+        Self::with_limits(|limits| limits)
+    }
+
+    /// Like [`new`](Self::new), with the device's limits adjusted by `limits`, such as lowered to
+    /// make the compressors split textures into several dispatches.
+    pub fn with_limits(limits: impl FnOnce(wgpu::Limits) -> wgpu::Limits) -> Option<Self> {
+        let backends = wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY);
+        let instance = wgpu::Instance::new(backends);
+
+        let adapter = pollster::block_on(wgpu::util::initialize_adapter_from_env_or_default(
+            &instance, backends, None,
+        ))?;
+
+        let timestamp_query = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+
+        let requirements = DeviceRequirements::new(&Configuration {
+            compressed_textures: adapter
+                .features()
+                .contains(wgpu::Features::TEXTURE_COMPRESSION_BC),
+            ..Configuration::for_adapter(&adapter)
+        });
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+
+                features: requirements.features | timestamp_query,
+                limits: limits(requirements.limits.using_resolution(adapter.limits())),
+            },
+            None,
+        ))
+        .ok()?;
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+        let compressor_2d = Compressor2D::new(&device);
+        let compressor_3d = Compressor3D::new(&device);
+
+        Some(Self {
+            device,
+            queue,
+            adapter_info: adapter.get_info(),
+            compressed_textures: requirements
+                .features
+                .contains(wgpu::Features::TEXTURE_COMPRESSION_BC),
+            sampler,
+            compressor_2d,
+            compressor_3d,
+        })
+    }
+
+    /// Compresses an image with [`Compressor2D`], or [`Compressor3D`] if it has a depth, and
+    /// waits for the device to finish. The blocks are left on the device.
+    pub fn compress(
+        &self,
+        texture: &wgpu::TextureView,
+        extent: wgpu::Extent3d,
+        quality: Quality,
+        profiler: Option<&Profiler>,
+    ) -> wgpu::Buffer {
+        self.compress_with(&CompressionParams {
+            bind_group_label: None,
+            texture,
+            sampler: &self.sampler,
+            extent,
+            quality,
+            source_mip_level: 0,
+            source_base_layer: 0,
+            profiler,
+            sanitization: Sanitization::default(),
+            corrected_texel_counter: None,
+        })
+    }
+
+    /// Like [`compress`](Self::compress), with all of the parameters. The profiler and the
+    /// corrected texel counter are resolved, ready to be read.
+    pub fn compress_with(&self, params: &CompressionParams) -> wgpu::Buffer {
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: compressed_size(params.extent),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        if params.extent.depth_or_array_layers > 1 {
+            self.compressor_3d.compress_to_buffer(
+                &self.device,
+                &mut command_encoder,
+                params,
+                &buffer,
+            );
+        } else {
+            self.compressor_2d.compress_to_buffer(
+                &self.device,
+                &mut command_encoder,
+                params,
+                &buffer,
+            );
+        }
+
+        if let Some(profiler) = params.profiler {
+            profiler.resolve(&mut command_encoder);
+        }
+
+        if let Some(counter) = params.corrected_texel_counter {
+            counter.resolve(&mut command_encoder);
+        }
+
+        self.queue.submit(Some(command_encoder.finish()));
+        self.device.poll(wgpu::Maintain::Wait);
+
+        buffer
+    }
+
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    /// Compresses an image and reads the blocks back.
+    pub fn compress_image(&self, image: &HdrImage, quality: Quality) -> Vec<u8> {
+        let texture = image
+            .create_texture(&self.device, &self.queue, None)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let extent = image.extent();
+        let buffer = self.compress(&texture, extent, quality, None);
+
+        self.read(&buffer, compressed_size(extent))
+    }
+
+    pub fn read(&self, buffer: &wgpu::Buffer, size: u64) -> Vec<u8> {
+        let mappable_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        command_encoder.copy_buffer_to_buffer(buffer, 0, &mappable_buffer, 0, size);
+        self.queue.submit(Some(command_encoder.finish()));
+
+        let slice = mappable_buffer.slice(..);
+        let map_future = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(map_future).unwrap();
+
+        let blocks = slice.get_mapped_range().to_vec();
+        mappable_buffer.unmap();
+        blocks
+    }
+}
+
+/// The size in bytes of the blocks that an extent compresses into, one byte per texel.
+pub fn compressed_size(extent: wgpu::Extent3d) -> u64 {
+    extent.width as u64 * extent.height as u64 * extent.depth_or_array_layers as u64
+}
+
+/// Copies `extent` texels from `origin` out of an image.
+pub fn crop(image: &HdrImage, origin: [u32; 3], extent: wgpu::Extent3d) -> HdrImage {
+    let mut pixels = Vec::new();
+
+    for z in origin[2]..origin[2] + extent.depth_or_array_layers {
+        for y in origin[1]..origin[1] + extent.height {
+            let row = ((z * image.height + y) * image.width + origin[0]) as usize;
+            pixels.extend_from_slice(&image.pixels[row..row + extent.width as usize]);
+        }
+    }
+
+    HdrImage::new(
+        extent.width,
+        extent.height,
+        extent.depth_or_array_layers,
+        pixels,
+    )
+}
@@ -0,0 +1,160 @@
+//! Checks the CPU decoder against blocks packed by hand from the BC6H format tables, with the
+//! texels they decode to worked out from the spec's unquantize and interpolation steps.
+
+use wgpu_bc6h_compression::decode;
+
+// Packs `(value, bit count)` fields into a block, least significant bit first.
+fn block(fields: &[(u128, u32)]) -> [u8; 16] {
+    let mut bits = 0_u128;
+    let mut position = 0;
+
+    for &(value, count) in fields {
+        assert!(value < 1 << count);
+        bits |= value << position;
+        position += count;
+    }
+
+    assert_eq!(position, 128);
+    bits.to_le_bytes()
+}
+
+#[test]
+fn one_region_block() {
+    // Mode 11: 10-bit endpoints from black to the largest value, and texel i at index i.
+    let mut fields = vec![
+        (0b00011, 5),
+        (0, 10),
+        (0, 10),
+        (0, 10),
+        (1023, 10),
+        (1023, 10),
+        (1023, 10),
+        // The anchor index has its top bit dropped.
+        (0, 3),
+    ];
+    fields.extend((1..16).map(|i| (i, 4)));
+
+    let texels = decode::decode_block(&block(&fields), false);
+
+    for texel in texels {
+        assert_eq!(texel[0], texel[1]);
+        assert_eq!(texel[0], texel[2]);
+    }
+
+    assert_eq!(texels[0][0], 0.0);
+    // A weight of 34 out of 64.
+    assert_eq!(texels[8][0], decode::f16_to_f32(0x41DF));
+    assert_eq!(texels[15][0], 65504.0);
+}
+
+#[test]
+fn two_region_block() {
+    // Mode 1 with partition 0, where the right two columns are the second region. Every endpoint
+    // is 495, which unquantizes to exactly 1.0, except for a red delta of 15 on the second
+    // endpoint of the first region.
+    let mut fields = vec![
+        (0b00, 2),
+        // gy[4], by[4] and bz[4].
+        (0, 3),
+        (495, 10),
+        (495, 10),
+        (495, 10),
+        // rx[4:0], then the rest of the deltas.
+        (15, 5),
+        (0, 37),
+        // The partition.
+        (0, 5),
+    ];
+    // Every index is 3, a weight of 27 out of 64. Texel 0 and texel 15, the anchor of the
+    // second region, have their top bit dropped.
+    fields.extend((0..16).map(|i| (3, if i == 0 || i == 15 { 2 } else { 3 })));
+
+    let texels = decode::decode_block(&block(&fields), false);
+
+    for (i, texel) in texels.iter().enumerate() {
+        let expected = if i % 4 < 2 {
+            // 495 and 510 interpolated.
+            [decode::f16_to_f32(0x3CC4), 1.0, 1.0]
+        } else {
+            [1.0; 3]
+        };
+
+        assert_eq!(*texel, expected, "texel {}", i);
+    }
+}
+
+#[test]
+fn signed_blocks_decode_like_unsigned_ones() {
+    // The mode bits of every mode and the bits of its endpoints.
+    const MODES: [(u128, u32); 14] = [
+        (0b00, 10),
+        (0b01, 7),
+        (0b00010, 11),
+        (0b00110, 11),
+        (0b01010, 11),
+        (0b01110, 9),
+        (0b10010, 8),
+        (0b10110, 8),
+        (0b11010, 8),
+        (0b11110, 6),
+        (0b00011, 10),
+        (0b00111, 11),
+        (0b01011, 12),
+        (0b01111, 16),
+    ];
+
+    // Every finite positive half, in order, to look the decoded texels up in.
+    let halves: Vec<f32> = (0..0x7C00).map(decode::f16_to_f32).collect();
+    let half_bits = |value: f32| halves.partition_point(|&half| half < value) as i32;
+
+    let mut state = 0x2545_F491_4F6C_DD1D_u64;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for (mode, endpoint_bits) in MODES {
+        let mode_bit_count = if mode < 2 { 2 } else { 5 };
+        // Losing a bit of endpoint precision moves the endpoints by up to half a step of the
+        // signed ones, with a little more from rounding the interpolation.
+        let tolerance = (0x7C00 >> (endpoint_bits - 1)) + 4;
+
+        let mut repacked = 0;
+
+        for _ in 0..256 {
+            let bits = (random() as u128) << 64 | random() as u128;
+            let unsigned = ((bits << mode_bit_count) | mode).to_le_bytes();
+
+            // Random deltas often wrap around, which signed endpoints can't.
+            let signed = match decode::to_signed_block(&unsigned) {
+                Some(signed) => signed,
+                None => continue,
+            };
+            repacked += 1;
+
+            let expected = decode::decode_block(&unsigned, false);
+            let texels = decode::decode_block(&signed, true);
+
+            for (texel, expected) in texels.iter().zip(&expected) {
+                for (value, expected) in texel.iter().zip(expected) {
+                    assert!(*value >= 0.0, "mode {:#b}: {} is negative", mode, value);
+                    assert!(
+                        (half_bits(*value) - half_bits(*expected)).abs() <= tolerance,
+                        "mode {:#b}: {} instead of {}",
+                        mode,
+                        value,
+                        expected
+                    );
+                }
+            }
+        }
+
+        assert!(
+            repacked > 0,
+            "mode {:#b}: no block could be re-packed",
+            mode
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Regression tests that compress the synthetic images in `common` and check the decoded blocks
+//! against error thresholds, so that edits to the shaders or a different naga can't quietly make
+//! the output worse.
+//!
+//! They need an adapter, but not a GPU: CI runs them on lavapipe. Without an adapter they are
+//! skipped, unless `BC6H_REQUIRE_ADAPTER` is set.
+
+mod common;
+
+use common::{ErrorMetrics, Gpu, Scene};
+use wgpu_bc6h_compression::{decode, HdrImage, Quality};
+
+const QUALITIES: [Quality; 6] = [
+    Quality::Fast,
+    Quality::Normal,
+    refined(4, false, 0),
+    refined(4, true, 0),
+    refined(4, true, 2),
+    Quality::High,
+];
+
+/// Pairs of a quality and a baseline whose candidate encodings it tries too. The shader keeps the
+/// candidate with the lowest MSLE in each block, so the quality can only lower the MSLE.
+const NO_WORSE_THAN: [(Quality, Quality); 7] = [
+    // P2 candidates are only kept where they beat the P1 one.
+    (Quality::Normal, Quality::Fast),
+    // The best scoring partition is one of the best 4.
+    (refined(4, false, 0), Quality::Normal),
+    // The optimized endpoints are tried after the plain ones.
+    (refined(1, true, 0), refined(1, false, 0)),
+    (refined(4, true, 0), refined(4, false, 0)),
+    // The first pass keeps the endpoints and only picks indices by their actual error.
+    (refined(1, false, 2), refined(1, false, 0)),
+    (refined(1, true, 2), refined(1, true, 0)),
+    // Every partition is tried, with the optimized endpoints and refinement.
+    (Quality::High, Quality::Normal),
+];
+
+const fn refined(partitions: u32, optimize_endpoints: bool, refinement_passes: u32) -> Quality {
+    Quality::Refined {
+        partitions,
+        optimize_endpoints,
+        refinement_passes,
+    }
+}
+
+// The largest `log_rmse` of each of `QUALITIES` over both sizes, as measured with llvmpipe and
+// rounded up by about 5%, so that any real loss of quality fails.
+fn max_log_rmse(scene: Scene) -> [f64; 6] {
+    match scene {
+        Scene::Gradient => [0.092, 0.063, 0.062, 0.057, 0.055, 0.054],
+        // A single line through 16 random colours can't get much closer than this.
+        Scene::Noise => [0.73, 0.68, 0.68, 0.63, 0.61, 0.61],
+        Scene::SunDisk => [0.031, 0.031, 0.031, 0.031, 0.031, 0.030],
+        Scene::ConstantBlocks => [0.0044, 0.0041, 0.0041, 0.0041, 0.0041, 0.0041],
+    }
+}
+
+fn errors(gpu: &Gpu, image: &HdrImage, quality: Quality) -> ErrorMetrics {
+    let blocks = gpu.compress_image(image, quality);
+
+    ErrorMetrics::measure(
+        image,
+        &decode::decode_blocks(&blocks, image.extent(), false),
+    )
+}
+
+#[test]
+fn decoded_error_is_below_threshold() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    for scene in Scene::ALL {
+        for (width, height, depth) in [(64, 64, 1), (32, 32, 4)] {
+            let image = scene.image(width, height, depth);
+
+            for (quality, max_log_rmse) in QUALITIES.into_iter().zip(max_log_rmse(scene)) {
+                let errors = errors(&gpu, &image, quality);
+
+                assert!(
+                    errors.log_rmse <= max_log_rmse,
+                    "{} at {:?} over {}x{}x{}: {:?}",
+                    scene.name(),
+                    quality,
+                    width,
+                    height,
+                    depth,
+                    errors
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn constant_blocks_are_nearly_exact() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let image = Scene::ConstantBlocks.image(64, 64, 1);
+
+    for quality in QUALITIES {
+        let errors = errors(&gpu, &image, quality);
+
+        // The single-region endpoints are quantized to 10 bits of a half. The two-region modes
+        // have fewer, and are kept where they lower the MSLE, which weights the channels by their
+        // luminance and so allows more error in blue.
+        let max_relative_error = if quality == Quality::Fast {
+            0.016
+        } else {
+            0.075
+        };
+
+        assert!(
+            errors.max_relative_error < max_relative_error,
+            "{:?}: {:?}",
+            quality,
+            errors
+        );
+    }
+}
+
+#[test]
+fn qualities_are_no_worse_than_their_baselines() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    for scene in Scene::ALL {
+        for (width, height, depth) in [(64, 64, 1), (32, 32, 4)] {
+            let image = scene.image(width, height, depth);
+
+            for (quality, baseline) in NO_WORSE_THAN {
+                let quality_errors = errors(&gpu, &image, quality);
+                let baseline_errors = errors(&gpu, &image, baseline);
+
+                assert!(
+                    quality_errors.msle <= baseline_errors.msle,
+                    "{} over {}x{}x{}: {:?} {:?} against {:?} {:?}",
+                    scene.name(),
+                    width,
+                    height,
+                    depth,
+                    quality,
+                    quality_errors,
+                    baseline,
+                    baseline_errors
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn one_refined_partition_matches_normal() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    for scene in Scene::ALL {
+        let image = scene.image(64, 64, 1);
+
+        // The same pattern is encoded the same way as with `Normal`.
+        assert!(
+            gpu.compress_image(&image, refined(1, false, 0))
+                == gpu.compress_image(&image, Quality::Normal),
+            "{}",
+            scene.name()
+        );
+    }
+}
+
+#[test]
+fn refined_options_change_the_encoding() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    // Each option lowers the error of some blocks of noise, so a shader that ignored its flag
+    // would encode the image like `Normal`, and the comparisons above would hold trivially.
+    let image = Scene::Noise.image(64, 64, 1);
+    let normal = gpu.compress_image(&image, Quality::Normal);
+
+    for quality in [
+        refined(4, false, 0),
+        refined(1, true, 0),
+        refined(1, false, 2),
+    ] {
+        assert!(
+            gpu.compress_image(&image, quality) != normal,
+            "{:?}",
+            quality
+        );
+    }
+}
+
+#[test]
+fn volumes_compress_like_their_slices() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let (width, height, depth) = (32, 16, 5);
+
+    for scene in Scene::ALL {
+        let volume = scene.image(width, height, depth);
+        let volume_blocks = gpu.compress_image(&volume, Quality::Normal);
+
+        // BC6H takes a byte per texel, so a slice has as many bytes of blocks as it has texels.
+        let slice_len = (width * height) as usize;
+
+        for (z, slice_blocks) in volume_blocks.chunks_exact(slice_len).enumerate() {
+            let slice = HdrImage::new(
+                width,
+                height,
+                1,
+                volume.pixels[z * slice_len..(z + 1) * slice_len].to_vec(),
+            );
+
+            assert!(
+                gpu.compress_image(&slice, Quality::Normal) == slice_blocks,
+                "slice {} of {} differs",
+                z,
+                scene.name()
+            );
+        }
+    }
+}
+
+#[test]
+fn compression_is_deterministic() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let image = Scene::SunDisk.image(64, 64, 1);
+
+    assert!(
+        gpu.compress_image(&image, Quality::Normal) == gpu.compress_image(&image, Quality::Normal)
+    );
+}
@@ -0,0 +1,29 @@
+//! Checks the box filter that `HdrImage::downsampled` generates mip levels with.
+
+use wgpu_bc6h_compression::HdrImage;
+
+fn gray(values: &[f32]) -> Vec<[f32; 4]> {
+    values
+        .iter()
+        .map(|&value| [value, value, value, 1.0])
+        .collect()
+}
+
+#[test]
+fn odd_rows_and_columns_are_folded_into_the_last_texel() {
+    let image = HdrImage::new(3, 1, 1, gray(&[1.0, 2.0, 6.0]));
+
+    assert_eq!(image.downsampled(), HdrImage::new(1, 1, 1, gray(&[3.0])));
+}
+
+#[test]
+fn empty_images_stay_empty() {
+    for ((width, height), halved) in [((0, 0), (0, 0)), ((0, 5), (0, 2)), ((6, 0), (3, 0))] {
+        let image = HdrImage::new(width, height, 1, Vec::new());
+
+        assert_eq!(
+            image.downsampled(),
+            HdrImage::new(halved.0, halved.1, 1, Vec::new())
+        );
+    }
+}
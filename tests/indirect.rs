@@ -0,0 +1,162 @@
+//! Checks that `compress_to_buffer_indirect` compresses the extent written to a buffer, like
+//! `compress_to_buffer` would with that extent. Skipped without an adapter unless
+//! `BC6H_REQUIRE_ADAPTER` is set.
+
+mod common;
+
+use common::{Gpu, Scene};
+use wgpu_bc6h_compression::{
+    indirect::IndirectExtent, sanitization::Sanitization, CompressionParams, Compressor2D,
+    Compressor3D, HdrImage, Quality,
+};
+
+fn extent(width: u32, height: u32, depth_or_array_layers: u32) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers,
+    }
+}
+
+// Compresses an image of `max_extent` with `indirect_extent` written to the extent buffer, and
+// returns it with the whole output buffer.
+fn compress_indirect(
+    gpu: &Gpu,
+    max_extent: wgpu::Extent3d,
+    indirect_extent: IndirectExtent,
+) -> (HdrImage, Vec<u8>) {
+    let image = Scene::SunDisk.image(
+        max_extent.width,
+        max_extent.height,
+        max_extent.depth_or_array_layers,
+    );
+    let texture = image
+        .create_texture(&gpu.device, &gpu.queue, None)
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    // Written by the queue here, but it could as well come from a shader.
+    let extent_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 12,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    gpu.queue.write_buffer(
+        &extent_buffer,
+        0,
+        bytemuck::cast_slice(&[
+            indirect_extent.width_in_blocks,
+            indirect_extent.height_in_blocks,
+            indirect_extent.depth,
+        ]),
+    );
+
+    let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: common::compressed_size(max_extent),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    });
+
+    let params = CompressionParams {
+        bind_group_label: None,
+        texture: &texture,
+        sampler: gpu.sampler(),
+        extent: max_extent,
+        quality: Quality::Normal,
+        source_mip_level: 0,
+        source_base_layer: 0,
+        profiler: None,
+        sanitization: Sanitization::default(),
+        corrected_texel_counter: None,
+    };
+
+    let mut command_encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    if max_extent.depth_or_array_layers > 1 {
+        Compressor3D::new(&gpu.device).compress_to_buffer_indirect(
+            &gpu.device,
+            &mut command_encoder,
+            &params,
+            &extent_buffer,
+            0,
+            &buffer,
+        );
+    } else {
+        Compressor2D::new(&gpu.device).compress_to_buffer_indirect(
+            &gpu.device,
+            &mut command_encoder,
+            &params,
+            &extent_buffer,
+            0,
+            &buffer,
+        );
+    }
+
+    gpu.queue.submit(Some(command_encoder.finish()));
+
+    (
+        image,
+        gpu.read(&buffer, common::compressed_size(max_extent)),
+    )
+}
+
+#[test]
+fn extent_is_read_from_the_buffer() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    for (max_extent, extent) in [
+        (extent(64, 64, 1), extent(64, 64, 1)),
+        (extent(64, 64, 1), extent(36, 20, 1)),
+        (extent(32, 32, 6), extent(32, 32, 6)),
+        (extent(32, 32, 6), extent(16, 8, 3)),
+    ] {
+        let (image, blocks) = compress_indirect(
+            &gpu,
+            max_extent,
+            IndirectExtent {
+                width_in_blocks: extent.width / 4,
+                height_in_blocks: extent.height / 4,
+                depth: extent.depth_or_array_layers,
+            },
+        );
+
+        let expected =
+            gpu.compress_image(&common::crop(&image, [0, 0, 0], extent), Quality::Normal);
+
+        assert!(
+            blocks[..expected.len()] == expected[..],
+            "{:?} out of {:?}",
+            extent,
+            max_extent
+        );
+    }
+}
+
+#[test]
+fn extent_is_clamped_to_the_largest() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let max_extent = extent(16, 16, 4);
+
+    // Twice the size in each direction, which would write past the end of the buffer.
+    let (image, blocks) = compress_indirect(
+        &gpu,
+        max_extent,
+        IndirectExtent {
+            width_in_blocks: 8,
+            height_in_blocks: 8,
+            depth: 8,
+        },
+    );
+
+    assert!(blocks == gpu.compress_image(&image, Quality::Normal));
+}
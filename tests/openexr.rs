@@ -0,0 +1,98 @@
+//! Checks that EXR images written with the `exr` crate are read back into the same texels.
+
+#![cfg(feature = "exr")]
+
+use exr::prelude::{f16, AnyChannel, AnyChannels, FlatSamples, Image, SmallVec, WritableImage};
+use wgpu_bc6h_compression::{
+    openexr::{read_exr_from_reader, ExrError},
+    HdrImage,
+};
+
+const WIDTH: usize = 3;
+const HEIGHT: usize = 2;
+
+// A tiny fixture, with the given channels filled by `sample(channel_index, texel_index)`.
+fn write_exr(channels: &[&str], sample: impl Fn(usize, usize) -> f32) -> Vec<u8> {
+    let channels = channels
+        .iter()
+        .enumerate()
+        .map(|(channel, name)| {
+            let samples = (0..WIDTH * HEIGHT).map(|i| sample(channel, i));
+
+            // Mix half and float channels.
+            let samples = if channel % 2 == 0 {
+                FlatSamples::F32(samples.collect())
+            } else {
+                FlatSamples::F16(samples.map(f16::from_f32).collect())
+            };
+
+            AnyChannel::new(*name, samples)
+        })
+        .collect();
+
+    let image = Image::from_channels(
+        (WIDTH, HEIGHT),
+        AnyChannels::sort(SmallVec::from_vec(channels)),
+    );
+
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image.write().to_buffered(&mut bytes).unwrap();
+    bytes.into_inner()
+}
+
+// Values a half can hold exactly, different for every channel and texel.
+fn sample(channel: usize, texel: usize) -> f32 {
+    (channel * 16 + texel) as f32 * 0.25
+}
+
+#[test]
+fn rgb_channels_are_read() {
+    let bytes = write_exr(&["R", "G", "B"], sample);
+
+    let image = read_exr_from_reader(std::io::Cursor::new(bytes), None).unwrap();
+
+    // Without an alpha channel, it is filled with 1.0.
+    let pixels = (0..WIDTH * HEIGHT)
+        .map(|i| [sample(0, i), sample(1, i), sample(2, i), 1.0])
+        .collect();
+
+    assert_eq!(image, HdrImage::new(WIDTH as u32, HEIGHT as u32, 1, pixels));
+}
+
+#[test]
+fn prefixed_channels_are_read() {
+    // Sorted alphabetically, the unprefixed channels come first.
+    let names = [
+        "B",
+        "G",
+        "R",
+        "diffuse.A",
+        "diffuse.B",
+        "diffuse.G",
+        "diffuse.R",
+    ];
+    let bytes = write_exr(&names, sample);
+
+    let image = read_exr_from_reader(std::io::Cursor::new(bytes), Some("diffuse")).unwrap();
+
+    let pixels = (0..WIDTH * HEIGHT)
+        .map(|i| [sample(6, i), sample(5, i), sample(4, i), sample(3, i)])
+        .collect();
+
+    assert_eq!(image, HdrImage::new(WIDTH as u32, HEIGHT as u32, 1, pixels));
+
+    assert!(matches!(
+        read_exr_from_reader(std::io::Cursor::new(write_exr(&names, sample)), Some("specular")),
+        Err(ExrError::LayerNotFound(layer)) if layer == "specular"
+    ));
+}
+
+#[test]
+fn missing_channels_are_reported() {
+    let bytes = write_exr(&["G", "R"], sample);
+
+    assert!(matches!(
+        read_exr_from_reader(std::io::Cursor::new(bytes), None),
+        Err(ExrError::MissingChannel(channel)) if channel == "B"
+    ));
+}
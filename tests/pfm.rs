@@ -0,0 +1,44 @@
+//! Checks that PFM images survive being written and read back.
+
+use wgpu_bc6h_compression::{pfm, HdrImage};
+
+fn round_trip(image: &HdrImage) -> HdrImage {
+    let mut bytes = Vec::new();
+    pfm::write_pfm(&mut bytes, image).unwrap();
+    pfm::read_pfm(&bytes[..]).unwrap()
+}
+
+#[test]
+fn written_images_read_back() {
+    // Not square, so swapped rows or columns show up. The alpha channel isn't stored.
+    let pixels = (0..5 * 3)
+        .map(|i| [i as f32, 0.5 / (i + 1) as f32, 1.0e4 * i as f32, 1.0])
+        .collect();
+    let image = HdrImage::new(5, 3, 1, pixels);
+
+    assert_eq!(round_trip(&image), image);
+}
+
+#[test]
+fn empty_images_read_back() {
+    for (width, height) in [(0, 0), (0, 2), (2, 0)] {
+        let image = HdrImage::new(width, height, 1, Vec::new());
+
+        assert_eq!(round_trip(&image), image);
+    }
+}
+
+#[test]
+fn greyscale_is_expanded() {
+    // A big-endian 2x1 `Pf`, which has a positive scale.
+    let mut bytes = b"Pf\n2 1\n1.0\n".to_vec();
+    bytes.extend(0.25_f32.to_be_bytes());
+    bytes.extend(8.0_f32.to_be_bytes());
+
+    let image = pfm::read_pfm(&bytes[..]).unwrap();
+
+    assert_eq!(
+        image,
+        HdrImage::new(2, 1, 1, vec![[0.25, 0.25, 0.25, 1.0], [8.0, 8.0, 8.0, 1.0]])
+    );
+}
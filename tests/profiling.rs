@@ -0,0 +1,24 @@
+//! Checks the throughput that `PassTiming` reports.
+
+use std::time::Duration;
+use wgpu_bc6h_compression::profiling::PassTiming;
+
+#[test]
+fn blocks_per_second_divides_by_the_duration() {
+    let timing = PassTiming {
+        duration: Duration::from_millis(250),
+        blocks: 1000,
+    };
+
+    assert_eq!(timing.blocks_per_second(), Some(4000.0));
+}
+
+#[test]
+fn passes_that_took_no_time_have_no_throughput() {
+    let timing = PassTiming {
+        duration: Duration::ZERO,
+        blocks: 1000,
+    };
+
+    assert_eq!(timing.blocks_per_second(), None);
+}
@@ -0,0 +1,58 @@
+use wgpu_bc6h_compression::requirements::{Configuration, DeviceRequirements};
+
+#[test]
+fn texture_compression_is_only_required_for_textures() {
+    let buffers = DeviceRequirements::new(&Configuration::default());
+    let textures = DeviceRequirements::new(&Configuration {
+        compressed_textures: true,
+        ..Default::default()
+    });
+
+    assert!(!buffers
+        .features
+        .contains(wgpu::Features::TEXTURE_COMPRESSION_BC));
+    assert!(textures
+        .features
+        .contains(wgpu::Features::TEXTURE_COMPRESSION_BC));
+}
+
+#[test]
+fn push_constants_are_only_required_when_configured() {
+    for push_constants in [false, true] {
+        let requirements = DeviceRequirements::new(&Configuration {
+            push_constants,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            requirements
+                .features
+                .contains(wgpu::Features::PUSH_CONSTANTS),
+            push_constants
+        );
+        assert_eq!(
+            requirements.limits.max_push_constant_size,
+            if push_constants { 32 } else { 0 }
+        );
+    }
+}
+
+#[test]
+fn raising_limits_only_touches_what_the_compressors_need() {
+    let requirements = DeviceRequirements::new(&Configuration::default());
+
+    let limits = requirements.raise_limits(wgpu::Limits {
+        max_texture_dimension_2d: 16384,
+        max_storage_buffers_per_shader_stage: 1,
+        max_compute_workgroup_size_z: 256,
+        ..wgpu::Limits::downlevel_webgl2_defaults()
+    });
+
+    assert_eq!(limits.max_texture_dimension_2d, 16384);
+    assert_eq!(limits.max_storage_buffers_per_shader_stage, 3);
+    assert_eq!(limits.max_compute_workgroup_size_z, 256);
+    assert_eq!(limits.max_compute_invocations_per_workgroup, 64);
+    // The workgroups of `Quality::High`.
+    assert_eq!(limits.max_compute_workgroup_size_x, 32);
+    assert_eq!(limits.max_compute_workgroup_storage_size, 896);
+}
@@ -0,0 +1,151 @@
+//! Checks that texels an unsigned half can't represent are corrected and counted. Like the golden
+//! tests, these are skipped without an adapter unless `BC6H_REQUIRE_ADAPTER` is set.
+
+mod common;
+
+use common::{Gpu, Scene};
+use wgpu_bc6h_compression::{
+    decode,
+    sanitization::{CorrectedTexelCounter, Sanitization},
+    CompressionParams, HdrImage, Quality,
+};
+
+// Constant blocks with a bad value in a few of them, one texel each.
+fn image_with_bad_texels() -> (HdrImage, Vec<usize>) {
+    let mut image = Scene::ConstantBlocks.image(16, 16, 1);
+    let bad_texels = vec![0, 21, 70, 133, 255];
+
+    image.pixels[0][0] = f32::NAN;
+    image.pixels[21][1] = -2.0;
+    image.pixels[70][2] = f32::INFINITY;
+    image.pixels[133][0] = 1.0e6;
+    image.pixels[255] = [f32::NAN, f32::NEG_INFINITY, f32::NAN, 1.0];
+
+    (image, bad_texels)
+}
+
+fn compress(
+    gpu: &Gpu,
+    image: &HdrImage,
+    sanitization: Sanitization,
+    counter: &CorrectedTexelCounter,
+) -> HdrImage {
+    let texture = image
+        .create_texture(&gpu.device, &gpu.queue, None)
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    let buffer = gpu.compress_with(&CompressionParams {
+        bind_group_label: None,
+        texture: &texture,
+        sampler: gpu.sampler(),
+        extent: image.extent(),
+        quality: Quality::Normal,
+        source_mip_level: 0,
+        source_base_layer: 0,
+        profiler: None,
+        sanitization,
+        corrected_texel_counter: Some(counter),
+    });
+
+    let blocks = gpu.read(&buffer, common::compressed_size(image.extent()));
+
+    decode::decode_blocks(&blocks, image.extent(), false)
+}
+
+#[test]
+fn bad_texels_are_corrected_and_counted() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let (image, bad_texels) = image_with_bad_texels();
+
+    for sanitization in [
+        Sanitization::ReplaceNanWithZero,
+        Sanitization::ReplaceNanWithNeighbourAverage,
+    ] {
+        let counter = CorrectedTexelCounter::new(&gpu.device);
+        let decoded = compress(&gpu, &image, sanitization, &counter);
+
+        assert_eq!(
+            pollster::block_on(counter.read(&gpu.device)).unwrap(),
+            bad_texels.len() as u32,
+            "{:?}",
+            sanitization
+        );
+
+        for (i, pixel) in decoded.pixels.iter().enumerate() {
+            assert!(
+                pixel[..3]
+                    .iter()
+                    .all(|value| value.is_finite() && (0.0..=65504.0).contains(value)),
+                "{:?}: texel {} is {:?}",
+                sanitization,
+                i,
+                pixel
+            );
+        }
+    }
+}
+
+#[test]
+fn nans_are_replaced_with_the_block_average() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let original = Scene::ConstantBlocks.image(16, 16, 1);
+    let mut image = original.clone();
+    image.pixels[0][0] = f32::NAN;
+
+    let counter = CorrectedTexelCounter::new(&gpu.device);
+    let decoded = compress(
+        &gpu,
+        &image,
+        Sanitization::ReplaceNanWithNeighbourAverage,
+        &counter,
+    );
+
+    // The rest of the block is the same colour, so the NaN should become that colour too.
+    let expected = original.pixels[0][0];
+    let relative_error = (decoded.pixels[0][0] - expected).abs() / expected;
+
+    assert!(
+        relative_error < 0.07,
+        "{} instead of {}",
+        decoded.pixels[0][0],
+        expected
+    );
+}
+
+#[test]
+fn counter_accumulates_until_cleared() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let (image, bad_texels) = image_with_bad_texels();
+    let counter = CorrectedTexelCounter::new(&gpu.device);
+
+    compress(&gpu, &image, Sanitization::default(), &counter);
+    compress(&gpu, &image, Sanitization::default(), &counter);
+
+    assert_eq!(
+        pollster::block_on(counter.read(&gpu.device)).unwrap(),
+        bad_texels.len() as u32 * 2
+    );
+
+    let mut command_encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    counter.clear(&mut command_encoder);
+    gpu.queue.submit(Some(command_encoder.finish()));
+
+    compress(&gpu, &image, Sanitization::None, &counter);
+
+    // Nothing is corrected, and so nothing counted, without sanitization.
+    assert_eq!(pollster::block_on(counter.read(&gpu.device)).unwrap(), 0);
+}
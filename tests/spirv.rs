@@ -0,0 +1,123 @@
+//! Checks the SPIR-V checked into `shaders/compiled`. wgpu parses it with naga before it hands it
+//! to a backend, and panics if that fails, so it is parsed and validated the same way here.
+//!
+//! It also has to match what `shaders/spirv.rs` generates from the WGSL shaders. Run these tests
+//! with `BC6H_UPDATE_SPIRV` set to bring them back in line.
+
+#[path = "../shaders/spirv.rs"]
+mod spirv;
+
+// The offsets of the members of `Constants`, where the Rust side writes them.
+const CONSTANTS_2D: &[u32] = &[0, 8, 12, 16, 20];
+const CONSTANTS_3D: &[u32] = &[0, 12, 16, 24, 28];
+
+fn parse(name: &str, checked_in: &[u8], constant_offsets: &[u32]) {
+    // The options wgpu's `create_shader_module` uses.
+    let options = naga::front::spv::Options {
+        adjust_coordinate_space: false,
+        strict_capabilities: true,
+        block_ctx_dump_prefix: None,
+    };
+
+    let module = naga::front::spv::parse_u8_slice(checked_in, &options)
+        .unwrap_or_else(|error| panic!("shaders/compiled/{}: {:?}", name, error));
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .unwrap_or_else(|error| panic!("shaders/compiled/{}: {:?}", name, error));
+
+    // SPIR-V compiled before a member was added would still parse, but ignore it.
+    let constants = module
+        .global_variables
+        .iter()
+        .find(|(_, variable)| {
+            matches!(
+                variable.class,
+                naga::StorageClass::Uniform | naga::StorageClass::PushConstant
+            )
+        })
+        .map(|(_, variable)| variable.ty)
+        .unwrap_or_else(|| panic!("shaders/compiled/{} has no constants", name));
+
+    let members = |ty: naga::Handle<naga::Type>| match &module.types[ty].inner {
+        naga::TypeInner::Struct { members, .. } => members.clone(),
+        inner => panic!("shaders/compiled/{}: constants are a {:?}", name, inner),
+    };
+
+    // Some compilers wrap the struct in a block of its own.
+    let mut constants = members(constants);
+    if let [member] = &constants[..] {
+        if let naga::TypeInner::Struct { .. } = module.types[member.ty].inner {
+            constants = members(member.ty);
+        }
+    }
+
+    let offsets: Vec<_> = constants.iter().map(|member| member.offset).collect();
+
+    assert_eq!(
+        offsets, constant_offsets,
+        "shaders/compiled/{} has a different Constants layout, regenerate it",
+        name
+    );
+}
+
+fn check(name: &str, checked_in: &[u8]) {
+    let (_, entry_point, push_constants) = spirv::VARIANTS
+        .iter()
+        .find(|(variant, ..)| *variant == name)
+        .unwrap_or_else(|| panic!("shaders/spirv.rs has no variant {}", name));
+
+    let generated = spirv::compile(entry_point, *push_constants)
+        .unwrap_or_else(|error| panic!("shaders/compiled/{}: {}", name, error));
+
+    if std::env::var_os("BC6H_UPDATE_SPIRV").is_some() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/compiled/");
+        std::fs::write(format!("{}{}", path, name), generated).unwrap();
+    } else {
+        assert!(
+            checked_in == generated,
+            "shaders/compiled/{} doesn't match the WGSL shaders, run the tests with \
+             BC6H_UPDATE_SPIRV set to regenerate it",
+            name
+        );
+    }
+}
+
+macro_rules! check_variant {
+    ($test:ident, $name:literal, $constant_offsets:expr) => {
+        mod $test {
+            #[test]
+            fn parses() {
+                super::parse(
+                    $name,
+                    include_bytes!(concat!("../shaders/compiled/", $name)),
+                    $constant_offsets,
+                );
+            }
+
+            #[test]
+            fn matches_wgsl() {
+                super::check(
+                    $name,
+                    include_bytes!(concat!("../shaders/compiled/", $name)),
+                );
+            }
+        }
+    };
+}
+
+check_variant!(compiled_2d, "2d.comp.spv", super::CONSTANTS_2D);
+check_variant!(compiled_3d, "3d.comp.spv", super::CONSTANTS_3D);
+check_variant!(
+    compiled_2d_push_constants,
+    "2d_push_constants.comp.spv",
+    super::CONSTANTS_2D
+);
+check_variant!(
+    compiled_3d_push_constants,
+    "3d_push_constants.comp.spv",
+    super::CONSTANTS_3D
+);
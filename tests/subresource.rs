@@ -0,0 +1,116 @@
+//! Checks that `CompressionParams::source_mip_level` and `source_base_layer` pick what is loaded
+//! from a view of a whole texture. Skipped without an adapter unless `BC6H_REQUIRE_ADAPTER` is
+//! set.
+
+mod common;
+
+use common::{Gpu, Scene};
+use wgpu_bc6h_compression::{sanitization::Sanitization, CompressionParams, HdrImage, Quality};
+
+fn compress(
+    gpu: &Gpu,
+    texture: &wgpu::TextureView,
+    extent: wgpu::Extent3d,
+    source_mip_level: u32,
+    source_base_layer: u32,
+) -> Vec<u8> {
+    let buffer = gpu.compress_with(&CompressionParams {
+        bind_group_label: None,
+        texture,
+        sampler: gpu.sampler(),
+        extent,
+        quality: Quality::Normal,
+        source_mip_level,
+        source_base_layer,
+        profiler: None,
+        sanitization: Sanitization::default(),
+        corrected_texel_counter: None,
+    });
+
+    gpu.read(&buffer, common::compressed_size(extent))
+}
+
+#[test]
+fn mip_levels_are_loaded_from_one_view() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    // A different scene in each level, so that loading the wrong one can't go unnoticed.
+    let levels = [
+        Scene::Gradient.image(32, 32, 1),
+        Scene::SunDisk.image(16, 16, 1),
+    ];
+
+    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: levels[0].extent(),
+        mip_level_count: levels.len() as u32,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    });
+
+    for (mip_level, level) in levels.iter().enumerate() {
+        gpu.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: mip_level as u32,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&level.pixels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                // Four f32 channels per texel.
+                bytes_per_row: std::num::NonZeroU32::new(level.width * 16),
+                rows_per_image: None,
+            },
+            level.extent(),
+        );
+    }
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    for (mip_level, level) in levels.iter().enumerate() {
+        assert!(
+            compress(&gpu, &view, level.extent(), mip_level as u32, 0)
+                == gpu.compress_image(level, Quality::Normal),
+            "level {} differs",
+            mip_level
+        );
+    }
+}
+
+#[test]
+fn slices_are_loaded_from_the_base_layer() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+
+    let (width, height) = (16, 16);
+    let volume = Scene::Noise.image(width, height, 6);
+    let view = volume
+        .create_texture(&gpu.device, &gpu.queue, None)
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    let slice_len = (width * height) as usize;
+    let (base_layer, layer_count) = (3, 2);
+
+    let layers = HdrImage::new(
+        width,
+        height,
+        layer_count,
+        volume.pixels
+            [base_layer as usize * slice_len..(base_layer + layer_count) as usize * slice_len]
+            .to_vec(),
+    );
+
+    assert!(
+        compress(&gpu, &view, layers.extent(), 0, base_layer)
+            == gpu.compress_image(&layers, Quality::Normal)
+    );
+}
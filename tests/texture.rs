@@ -0,0 +1,110 @@
+//! Checks that `compress_into_texture` writes the same blocks as `compress_to_buffer`, at the mip
+//! level and layer it is given. Skipped without an adapter that can create BC6H textures.
+
+mod common;
+
+use common::Scene;
+use wgpu_bc6h_compression::{sanitization::Sanitization, CompressionParams, Compressor2D, Quality};
+
+#[test]
+fn blocks_are_written_at_the_destination() {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) if gpu.compressed_textures => gpu,
+        _ => return,
+    };
+
+    // Level 1 of a 32x32 array is 16x16, 4x4 blocks.
+    let image = Scene::SunDisk.image(16, 16, 1);
+    let extent = image.extent();
+    let expected = gpu.compress_image(&image, Quality::Normal);
+
+    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 32,
+            height: 32,
+            depth_or_array_layers: 3,
+        },
+        mip_level_count: 2,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Bc6hRgbUfloat,
+        usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+    });
+
+    let destination = wgpu::ImageCopyTexture {
+        texture: &texture,
+        mip_level: 1,
+        origin: wgpu::Origin3d { x: 0, y: 0, z: 2 },
+        aspect: wgpu::TextureAspect::All,
+    };
+
+    let source = image
+        .create_texture(&gpu.device, &gpu.queue, None)
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    // Rows of a copy out of a texture are padded to 256 bytes.
+    let padded_bytes_per_row = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let block_rows = extent.height / 4;
+
+    let readback_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (padded_bytes_per_row * block_rows) as u64,
+        usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let compressor = Compressor2D::new(&gpu.device);
+
+    let mut command_encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    compressor.compress_into_texture(
+        &gpu.device,
+        &mut command_encoder,
+        &CompressionParams {
+            bind_group_label: None,
+            texture: &source,
+            sampler: gpu.sampler(),
+            extent,
+            quality: Quality::Normal,
+            source_mip_level: 0,
+            source_base_layer: 0,
+            profiler: None,
+            sanitization: Sanitization::default(),
+            corrected_texel_counter: None,
+        },
+        destination,
+    );
+
+    command_encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 1,
+            origin: wgpu::Origin3d { x: 0, y: 0, z: 2 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &readback_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row),
+                rows_per_image: std::num::NonZeroU32::new(block_rows),
+            },
+        },
+        extent,
+    );
+
+    gpu.queue.submit(Some(command_encoder.finish()));
+
+    let padded = gpu.read(&readback_buffer, (padded_bytes_per_row * block_rows) as u64);
+    let bytes_per_row = (extent.width * 4) as usize;
+
+    let blocks: Vec<u8> = padded
+        .chunks_exact(padded_bytes_per_row as usize)
+        .flat_map(|row| row[..bytes_per_row].iter().copied())
+        .collect();
+
+    assert!(blocks == expected);
+}
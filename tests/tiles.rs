@@ -0,0 +1,46 @@
+//! Checks that textures split into several dispatches compress to the same blocks as in one, by
+//! lowering the limits that the tiles are sized from. Skipped without an adapter unless
+//! `BC6H_REQUIRE_ADAPTER` is set.
+
+mod common;
+
+use common::Scene;
+use wgpu_bc6h_compression::Quality;
+
+// Two workgroups per dimension fit 16 rows of blocks (2D) or 8 slices (3D) in a dispatch, and a
+// binding of 8 KiB fits fewer than 512 blocks, so that later tiles are bound at an offset.
+fn lower_limits(limits: wgpu::Limits) -> wgpu::Limits {
+    wgpu::Limits {
+        max_compute_workgroups_per_dimension: 2,
+        max_storage_buffer_binding_size: 8192,
+        ..limits
+    }
+}
+
+fn check(image: &wgpu_bc6h_compression::HdrImage) {
+    let gpu = match common::gpu_for_test() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+    let tiled_gpu = common::gpu_for_test_with_limits(lower_limits).unwrap();
+
+    for quality in [Quality::Fast, Quality::Normal] {
+        assert!(
+            gpu.compress_image(image, quality) == tiled_gpu.compress_image(image, quality),
+            "{:?} blocks differ when tiled",
+            quality
+        );
+    }
+}
+
+#[test]
+fn tiled_2d_compression_matches_one_dispatch() {
+    // 16 blocks wide and 64 tall, in at least 4 tiles.
+    check(&Scene::Gradient.image(64, 256, 1));
+}
+
+#[test]
+fn tiled_3d_compression_matches_one_dispatch() {
+    // 8 blocks wide and tall, over 16 slices in at least 2 tiles.
+    check(&Scene::SunDisk.image(32, 32, 16));
+}
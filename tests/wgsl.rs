@@ -0,0 +1,73 @@
+//! Parses and validates the WGSL shaders with naga, the same way wgpu does before it hands them
+//! to a backend, so that mistakes in them show up without a GPU.
+
+const ENCODER: &str = include_str!("../shaders/bc6h.wgsl");
+const COUNT_CORRECTED: &str = include_str!("../shaders/count_corrected.wgsl");
+
+fn validate(name: &str, source: &str) {
+    let module = naga::front::wgsl::parse_str(source)
+        .unwrap_or_else(|error| panic!("{}: {}", name, error.emit_to_string(source)));
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::PUSH_CONSTANT,
+    )
+    .validate(&module)
+    .unwrap_or_else(|error| panic!("{}: {:?}", name, error));
+}
+
+fn compressor_source(entry_point: &str, push_constants: bool) -> String {
+    let source = format!("{}\n{}\n{}", ENCODER, COUNT_CORRECTED, entry_point);
+
+    if push_constants {
+        let uniform = "[[group(0), binding(3)]]\nvar<uniform> constants: Constants;";
+        assert!(source.contains(uniform));
+        source.replace(uniform, "var<push_constant> constants: Constants;")
+    } else {
+        source
+    }
+}
+
+#[test]
+fn compressor_2d() {
+    let entry_point = include_str!("../shaders/compress_2d.wgsl");
+    validate("2d", &compressor_source(entry_point, false));
+    validate("2d push constants", &compressor_source(entry_point, true));
+}
+
+#[test]
+fn compressor_3d() {
+    let entry_point = include_str!("../shaders/compress_3d.wgsl");
+    validate("3d", &compressor_source(entry_point, false));
+    validate("3d push constants", &compressor_source(entry_point, true));
+}
+
+#[test]
+fn batch_compressor() {
+    let entry_point = include_str!("../shaders/compress_batch.wgsl");
+    validate("batch", &compressor_source(entry_point, false));
+    validate(
+        "batch push constants",
+        &compressor_source(entry_point, true),
+    );
+}
+
+#[test]
+fn auxiliary_shaders() {
+    validate(
+        "equirect_to_cubemap",
+        include_str!("../shaders/equirect_to_cubemap.wgsl"),
+    );
+    validate(
+        "indirect_arguments",
+        include_str!("../shaders/indirect_arguments.wgsl"),
+    );
+    validate(
+        "ggx_prefilter",
+        include_str!("../shaders/ggx_prefilter.wgsl"),
+    );
+    validate(
+        "sh9_irradiance",
+        include_str!("../shaders/sh9_irradiance.wgsl"),
+    );
+}